@@ -6,29 +6,48 @@ use dyn_clone::{clone_trait_object, DynClone};
 use itertools::*;
 use num_traits::{One, Zero};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::convert::TryInto;
 use std::{
     any::Any,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     error::Error,
     ops::{Add, AddAssign, Sub, SubAssign},
     sync::Arc,
 };
+pub mod arithmetization;
+pub mod batch_evaluator;
 pub mod bool_gate_type;
+pub mod bristol;
 pub mod builder;
+pub mod compose;
+pub mod dot;
 pub mod encode;
 pub mod evaluator;
+pub mod gadgets;
+pub mod lut;
+pub mod optimize;
+pub mod random;
+pub mod rebalance;
+pub mod registry;
+pub mod stats;
+pub mod validate;
 use bincode;
 use bool_gate_type::*;
 use builder::*;
 use encode::*;
 use evaluator::*;
+use registry::*;
 
 pub trait BoolGateType: std::fmt::Debug + DynClone + Downcast + Send + Sync {
     fn eval(&self, input: &[bool]) -> Vec<bool>;
     fn num_input(&self) -> usize;
     fn num_output(&self) -> usize;
     fn gate_type_id(&self) -> u64;
+    /// Returns the parameters of the gate type, which are passed to its constructor in [`BoolGateTypeRegistry`] when decoding.
+    fn params(&self) -> Vec<u8> {
+        vec![]
+    }
 }
 
 clone_trait_object!(BoolGateType);
@@ -129,6 +148,18 @@ impl BoolGate {
     }
 }
 
+impl Drop for BoolGate {
+    /// Drops the input gates iteratively so that dropping a deep circuit does not overflow the stack.
+    fn drop(&mut self) {
+        let mut stack = std::mem::take(&mut self.inputs);
+        while let Some(gate) = stack.pop() {
+            if let Ok(mut gate) = Arc::try_unwrap(gate) {
+                stack.append(&mut gate.inputs);
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct BoolCircuit {
     pub num_input: usize,
@@ -163,21 +194,49 @@ impl BoolCircuit {
         self.num_gates
     }
 
-    // pub fn circuit_digest<H: NativeHasher>(&self) -> [bool; DIGEST_BIT_SIZE] {
-    //     let mut gates = sort_gates(&self).into_iter().collect_vec();
-    //     gates.sort_by_key(|(gate_id, _)| *gate_id);
-    //     let sorted_gates = gates.into_iter().map(|(_, gate)| gate).collect_vec();
-
-    //     let mut circuit_digest = vec![];
-    //     for gate in sorted_gates.into_iter() {
-    //         let mut digest_input = circuit_digest.clone();
-    //         let gate_bytes = gate.to_bytes();
-    //         digest_input.append(&mut bytes2bits_le(&gate_bytes, gate_bytes.len()));
-    //         let mut hasher = H::new();
-    //         circuit_digest = hasher.digest(&digest_input);
-    //     }
-    //     circuit_digest.try_into().unwrap()
-    // }
+    /// Returns the SHA-256 digest of the circuit, which depends only on its structure and not on the [`Arc`] pointers of its gates.
+    /// The chips are flattened and the gates are renumbered in a canonical topological order before hashing the bytes of each [`EncodedBoolGate`].
+    pub fn digest(&self) -> [u8; 32] {
+        let encoded = EncodedBoolCircuit::from_circuit(&self.flatten_chips());
+        let mut hasher = Sha256::new();
+        hasher.update((encoded.num_input as u64).to_le_bytes());
+        hasher.update((encoded.gates.len() as u64).to_le_bytes());
+        for gate in encoded.gates.iter() {
+            hasher.update(gate.to_bytes());
+        }
+        hasher.update((encoded.output_gates.len() as u64).to_le_bytes());
+        for gate_id in encoded.output_gates.iter() {
+            hasher.update(gate_id.to_bytes());
+        }
+        hasher.finalize().into()
+    }
+}
+
+/// Returns all gates reachable from the output gates of `circuit` in a topological order, i.e., every gate appears after its inputs.
+/// Each gate appears only once even if it is referenced by multiple gates.
+pub fn sort_gates(circuit: &BoolCircuit) -> Vec<Arc<BoolGate>> {
+    let mut visited = HashSet::<GateId>::new();
+    let mut sorted_gates = vec![];
+    // Each entry is a gate and the index of its next input to visit.
+    let mut stack = Vec::<(Arc<BoolGate>, usize)>::new();
+    for output_gate in circuit.output_gates.iter() {
+        if !visited.insert(output_gate.gate_id) {
+            continue;
+        }
+        stack.push((output_gate.clone(), 0));
+        while let Some((gate, input_idx)) = stack.pop() {
+            if input_idx == gate.inputs.len() {
+                sorted_gates.push(gate);
+                continue;
+            }
+            let input_gate = gate.inputs[input_idx].clone();
+            stack.push((gate, input_idx + 1));
+            if visited.insert(input_gate.gate_id) {
+                stack.push((input_gate, 0));
+            }
+        }
+    }
+    sorted_gates
 }
 
 #[cfg(test)]
@@ -387,4 +446,48 @@ mod test {
         let output = evaluator.eval(&input);
         assert_eq!(output, expected_output(input[0], input[1], input[2]));
     }
+
+    fn digest_test_circuit(num_unused_gates: usize) -> BoolCircuit {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let input = circuit_builder.input(3);
+        for _ in 0..num_unused_gates {
+            circuit_builder.not(&input[0]);
+        }
+        let or = circuit_builder.or(&input[0], &input[1]);
+        let and = circuit_builder.and(&input[1], &input[2]);
+        let xor = circuit_builder.xor(&or, &and);
+        circuit_builder.output(vec![xor, and])
+    }
+
+    #[test]
+    fn test_digest() {
+        let digest = digest_test_circuit(0).digest();
+        // The digest is independent of the gate ids and the Arc pointers.
+        assert_eq!(digest_test_circuit(5).digest(), digest);
+        let decoded = BoolCircuit::from_bytes(&digest_test_circuit(0).to_bytes()).unwrap();
+        assert_eq!(decoded.digest(), digest);
+        // The digest must stay the same across builds.
+        assert_eq!(
+            hex::encode(digest),
+            "ca5c52838b5d4c635c891d65fc4dc0be9299cd4288b726188b9045ad9cd401a9"
+        );
+
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let input = circuit_builder.input(3);
+        let or = circuit_builder.or(&input[0], &input[1]);
+        let and = circuit_builder.and(&input[1], &input[2]);
+        let xor = circuit_builder.xor(&or, &and);
+        let swapped = circuit_builder.output(vec![and, xor]);
+        assert_ne!(swapped.digest(), digest);
+    }
+
+    #[test]
+    fn test_digest_with_chips() {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let chip = circuit_builder.register_chip(digest_test_circuit(0));
+        let input = circuit_builder.input(3);
+        let outputs = circuit_builder.call_chip(&input, &chip);
+        let circuit = circuit_builder.output(outputs);
+        assert_eq!(circuit.digest(), digest_test_circuit(0).digest());
+    }
 }
@@ -0,0 +1,273 @@
+use super::*;
+use crate::poly::{Monomial, Polynomial, Variable, VariableType};
+use ark_ff::PrimeField;
+use std::collections::BTreeMap;
+
+/// A multilinear polynomial over the public variables, keyed by the sorted variable indices of each monomial.
+/// Since every wire of [`BoolCircuit`] is boolean, `x_i^2` is always reduced to `x_i`.
+type MultilinearPoly<F> = BTreeMap<Vec<u32>, F>;
+
+impl BoolCircuit {
+    /// Arithmetizes the circuit into one polynomial per output gate in the public variables `x{i}`, where `x{i}` is the i-th input.
+    /// The gates are mapped as XOR -> a+b-2ab, AND -> ab, OR -> a+b-ab, NOT -> 1-a and the constants to 0 and 1, and every monomial is kept multilinear.
+    /// The chips are inlined by [`BoolCircuit::flatten_chips`] and the lookup tables are lowered by [`BoolCircuit::lower_luts`] in advance, so the gate ids in the errors are those of the resulting circuit.
+    /// If `max_degree` is given, it returns [`crate::Error::PolynomialDegreeExceeded`] as soon as the polynomial of some gate exceeds it.
+    pub fn to_polynomials<F: PrimeField>(
+        &self,
+        max_degree: Option<usize>,
+    ) -> Result<Vec<Polynomial<F>>, crate::Error> {
+        if self.has_chips() {
+            return self.flatten_chips().to_polynomials(max_degree);
+        }
+        if self.has_luts() {
+            return self.lower_luts().to_polynomials(max_degree);
+        }
+        let mut arithmetizer = Arithmetizer {
+            polys: HashMap::new(),
+            max_degree,
+        };
+        // The gates are visited in a topological order rather than recursively, so that a deep circuit does not overflow the stack.
+        for gate in sort_gates(self) {
+            arithmetizer.arithmetize_gate(&gate)?;
+        }
+        Ok(self
+            .output_gates
+            .iter()
+            .map(|gate| to_polynomial(&arithmetizer.polys[&gate.gate_id]))
+            .collect())
+    }
+}
+
+struct Arithmetizer<F: PrimeField> {
+    polys: HashMap<GateId, MultilinearPoly<F>>,
+    max_degree: Option<usize>,
+}
+
+impl<F: PrimeField> Arithmetizer<F> {
+    /// Arithmetizes `gate`, whose inputs must have been arithmetized already.
+    fn arithmetize_gate(&mut self, gate: &BoolGate) -> Result<(), crate::Error> {
+        let inputs = gate
+            .inputs
+            .iter()
+            .map(|input_gate| &self.polys[&input_gate.gate_id])
+            .collect_vec();
+        let gate_type_id = gate.gate_type.gate_type_id();
+        let poly = match gate_type_id {
+            id if id == BoolGateTypeId::Input as u64 => {
+                MultilinearPoly::from([(vec![gate.gate_id.id as u32], F::one())])
+            }
+            id if id == BoolGateTypeId::Output as u64 => inputs[0].clone(),
+            // As in the evaluators, the output of a multi-output gate is read at the id of the projection.
+            id if id == BoolGateTypeId::Projection as u64 => match self.polys.get(&gate.gate_id) {
+                Some(poly) => poly.clone(),
+                None => {
+                    return Err(crate::Error::UnsupportedGateType(
+                        gate.inputs[0].gate_type.gate_type_id(),
+                    ))
+                }
+            },
+            id if id == BoolGateTypeId::Xor as u64 => {
+                let mut poly = add(inputs[0], inputs[1], F::one());
+                let ab = mul(inputs[0], inputs[1]);
+                add_assign(&mut poly, &ab, -F::from(2u64));
+                poly
+            }
+            id if id == BoolGateTypeId::And as u64 => mul(inputs[0], inputs[1]),
+            id if id == BoolGateTypeId::Or as u64 => {
+                let mut poly = add(inputs[0], inputs[1], F::one());
+                let ab = mul(inputs[0], inputs[1]);
+                add_assign(&mut poly, &ab, -F::one());
+                poly
+            }
+            id if id == BoolGateTypeId::Not as u64 => {
+                let mut poly = MultilinearPoly::from([(vec![], F::one())]);
+                add_assign(&mut poly, inputs[0], -F::one());
+                poly
+            }
+            id if id == BoolGateTypeId::False as u64 => MultilinearPoly::new(),
+            id if id == BoolGateTypeId::True as u64 => MultilinearPoly::from([(vec![], F::one())]),
+            _ => return Err(crate::Error::UnsupportedGateType(gate_type_id)),
+        };
+        if let Some(max_degree) = self.max_degree {
+            let degree = poly.keys().map(|indices| indices.len()).max().unwrap_or(0);
+            if degree > max_degree {
+                return Err(crate::Error::PolynomialDegreeExceeded(
+                    gate.gate_id.id,
+                    degree,
+                    max_degree,
+                ));
+            }
+        }
+        self.polys.insert(gate.gate_id, poly);
+        Ok(())
+    }
+}
+
+/// Adds `coeff * rhs` to `lhs`.
+fn add_assign<F: PrimeField>(lhs: &mut MultilinearPoly<F>, rhs: &MultilinearPoly<F>, coeff: F) {
+    for (indices, rhs_coeff) in rhs.iter() {
+        *lhs.entry(indices.clone()).or_insert(F::zero()) += coeff * rhs_coeff;
+    }
+    lhs.retain(|_, coeff| !coeff.is_zero());
+}
+
+/// Returns `lhs + coeff * rhs`.
+fn add<F: PrimeField>(
+    lhs: &MultilinearPoly<F>,
+    rhs: &MultilinearPoly<F>,
+    coeff: F,
+) -> MultilinearPoly<F> {
+    let mut poly = lhs.clone();
+    add_assign(&mut poly, rhs, coeff);
+    poly
+}
+
+fn mul<F: PrimeField>(lhs: &MultilinearPoly<F>, rhs: &MultilinearPoly<F>) -> MultilinearPoly<F> {
+    let mut poly = MultilinearPoly::new();
+    for (lhs_indices, lhs_coeff) in lhs.iter() {
+        for (rhs_indices, rhs_coeff) in rhs.iter() {
+            let indices = lhs_indices
+                .iter()
+                .merge(rhs_indices.iter())
+                .dedup()
+                .copied()
+                .collect_vec();
+            *poly.entry(indices).or_insert(F::zero()) += *lhs_coeff * rhs_coeff;
+        }
+    }
+    poly.retain(|_, coeff| !coeff.is_zero());
+    poly
+}
+
+fn to_polynomial<F: PrimeField>(poly: &MultilinearPoly<F>) -> Polynomial<F> {
+    let monomials = poly
+        .iter()
+        .map(|(indices, coeff)| {
+            let variables = indices
+                .iter()
+                .map(|index| Variable {
+                    index: *index,
+                    variable_type: VariableType::Public,
+                })
+                .collect();
+            Monomial::new(variables, *coeff)
+        })
+        .collect();
+    Polynomial::new(monomials)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_bn254::Fr;
+    use std::str::FromStr;
+
+    fn assert_polys_match_circuit(circuit: &Arc<BoolCircuit>, polys: &[Polynomial<Fr>]) {
+        let num_input = circuit.num_input();
+        for bits in 0..(1 << num_input) {
+            let input = (0..num_input)
+                .map(|idx| (bits >> idx) & 1 == 1)
+                .collect_vec();
+            let mut evaluator = PlainBoolCircuitEvaluator::new(circuit.clone());
+            let output = evaluator.eval(&input);
+            let mut assignment = HashMap::new();
+            for (idx, bit) in input.iter().enumerate() {
+                assignment.insert(
+                    Variable {
+                        index: idx as u32,
+                        variable_type: VariableType::Public,
+                    },
+                    Fr::from(*bit as u64),
+                );
+            }
+            for (poly, bit) in polys.iter().zip(output.iter()) {
+                assert_eq!(poly.eval(&assignment), Fr::from(*bit as u64));
+            }
+        }
+    }
+
+    #[test]
+    fn test_single_gates() {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let input = circuit_builder.input(2);
+        let xor = circuit_builder.xor(&input[0], &input[1]);
+        let and = circuit_builder.and(&input[0], &input[1]);
+        let or = circuit_builder.or(&input[0], &input[1]);
+        let not = circuit_builder.not(&input[0]);
+        let circuit = Arc::new(circuit_builder.output(vec![xor, and, or, not]));
+        let polys = circuit.to_polynomials::<Fr>(None).unwrap();
+        assert_eq!(polys.len(), 4);
+        assert_eq!(
+            polys.iter().map(|poly| poly.degree()).collect_vec(),
+            vec![2, 2, 2, 1]
+        );
+        assert_polys_match_circuit(&circuit, &polys);
+    }
+
+    #[test]
+    fn test_multi_layer() {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let input = circuit_builder.input(3);
+        let or = circuit_builder.or(&input[0], &input[1]);
+        let and = circuit_builder.and(&input[1], &input[2]);
+        let xor = circuit_builder.xor(&or, &and);
+        let not = circuit_builder.not(&xor);
+        let and2 = circuit_builder.and(&not, &input[1]);
+        let circuit = Arc::new(circuit_builder.output(vec![xor, not, and2]));
+        let polys = circuit.to_polynomials::<Fr>(None).unwrap();
+        assert!(polys.iter().all(|poly| poly.degree() <= 3));
+        assert_polys_match_circuit(&circuit, &polys);
+    }
+
+    #[test]
+    fn test_max_degree() {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let input = circuit_builder.input(3);
+        let and1 = circuit_builder.and(&input[0], &input[1]);
+        let and2 = circuit_builder.and(&and1, &input[2]);
+        let circuit = circuit_builder.output(vec![and2]);
+        assert!(circuit.to_polynomials::<Fr>(Some(3)).is_ok());
+        assert!(matches!(
+            circuit.to_polynomials::<Fr>(Some(2)),
+            Err(crate::Error::PolynomialDegreeExceeded(4, 3, 2))
+        ));
+    }
+
+    #[test]
+    fn test_chips_and_luts() {
+        let mut chip_builder = BoolCircuitBuilder::new();
+        let chip_input = chip_builder.input(2);
+        let xor = chip_builder.xor(&chip_input[0], &chip_input[1]);
+        let and = chip_builder.and(&chip_input[0], &chip_input[1]);
+        let chip = chip_builder.output(vec![xor, and]);
+
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let input = circuit_builder.input(3);
+        let chip = circuit_builder.register_chip(chip);
+        let half_adder = circuit_builder.call_chip(&input[0..2], &chip);
+        let mut outputs = circuit_builder
+            .lut_from_fn(&[half_adder[0].clone(), input[2].clone()], 2, |row| {
+                (row * 3) % 4
+            })
+            .unwrap();
+        outputs.push(half_adder[1].clone());
+        let circuit = Arc::new(circuit_builder.output(outputs));
+        let polys = circuit.to_polynomials::<Fr>(None).unwrap();
+        assert_eq!(polys.len(), 3);
+        assert_polys_match_circuit(&circuit, &polys);
+    }
+
+    #[test]
+    fn test_deep_circuit() {
+        let depth = 1_000_000;
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let input = circuit_builder.input(1);
+        let mut gate = input[0].clone();
+        for _ in 0..depth {
+            gate = circuit_builder.not(&gate);
+        }
+        let circuit = Arc::new(circuit_builder.output(vec![gate]));
+        let polys = circuit.to_polynomials::<Fr>(None).unwrap();
+        assert_eq!(polys[0], Polynomial::from_str("x0").unwrap());
+    }
+}
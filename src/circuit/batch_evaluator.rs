@@ -0,0 +1,248 @@
+use super::*;
+use rayon::prelude::*;
+use std::marker::PhantomData;
+
+/// A word packing the values of one wire for [`BitSliceWord::NUM_LANES`] independent inputs, one bit per lane.
+pub trait BitSliceWord: Copy + Send + Sync {
+    const NUM_LANES: usize;
+
+    fn zero() -> Self;
+    fn xor(self, other: Self) -> Self;
+    fn and(self, other: Self) -> Self;
+    fn or(self, other: Self) -> Self;
+    fn not(self) -> Self;
+    fn get_lane(&self, lane: usize) -> bool;
+    fn set_lane(&mut self, lane: usize, bit: bool);
+}
+
+impl BitSliceWord for u64 {
+    const NUM_LANES: usize = 64;
+
+    fn zero() -> Self {
+        0
+    }
+
+    fn xor(self, other: Self) -> Self {
+        self ^ other
+    }
+
+    fn and(self, other: Self) -> Self {
+        self & other
+    }
+
+    fn or(self, other: Self) -> Self {
+        self | other
+    }
+
+    fn not(self) -> Self {
+        !self
+    }
+
+    fn get_lane(&self, lane: usize) -> bool {
+        (self >> lane) & 1 == 1
+    }
+
+    fn set_lane(&mut self, lane: usize, bit: bool) {
+        *self = (*self & !(1 << lane)) | ((bit as u64) << lane);
+    }
+}
+
+/// `[u64; 2]` and `[u64; 4]` give 128 and 256 lanes respectively.
+impl<const N: usize> BitSliceWord for [u64; N] {
+    const NUM_LANES: usize = 64 * N;
+
+    fn zero() -> Self {
+        [0; N]
+    }
+
+    fn xor(self, other: Self) -> Self {
+        std::array::from_fn(|idx| self[idx] ^ other[idx])
+    }
+
+    fn and(self, other: Self) -> Self {
+        std::array::from_fn(|idx| self[idx] & other[idx])
+    }
+
+    fn or(self, other: Self) -> Self {
+        std::array::from_fn(|idx| self[idx] | other[idx])
+    }
+
+    fn not(self) -> Self {
+        std::array::from_fn(|idx| !self[idx])
+    }
+
+    fn get_lane(&self, lane: usize) -> bool {
+        self[lane / 64].get_lane(lane % 64)
+    }
+
+    fn set_lane(&mut self, lane: usize, bit: bool) {
+        self[lane / 64].set_lane(lane % 64, bit);
+    }
+}
+
+/// An evaluator of [`BoolCircuit`] that evaluates [`BitSliceWord::NUM_LANES`] inputs at once by evaluating XOR/AND/OR/NOT and constant gates bitwise over words.
+/// Gates of the other types are evaluated lane by lane with [`BoolGateType::eval`].
+pub struct BatchBoolCircuitEvaluator<W: BitSliceWord> {
+    pub circuit: Arc<BoolCircuit>,
+    flat_circuit: FlatCircuit,
+    _w: PhantomData<W>,
+}
+
+impl<W: BitSliceWord> BatchBoolCircuitEvaluator<W> {
+    pub fn new(circuit: Arc<BoolCircuit>) -> Self {
+        let flat_circuit = FlatCircuit::new(&circuit);
+        Self {
+            circuit,
+            flat_circuit,
+            _w: PhantomData,
+        }
+    }
+
+    /// Evaluates the circuit on the bit-sliced `input`, where the i-th word holds the i-th input bit of every lane.
+    /// `wires` is a buffer reused across calls.
+    pub fn eval_words(&self, input: &[W], wires: &mut Vec<W>) -> Vec<W> {
+        debug_assert_eq!(input.len(), self.circuit.num_input);
+        wires.resize(self.flat_circuit.num_wires, W::zero());
+        wires[..input.len()].copy_from_slice(input);
+        let mut gate_input = vec![];
+        for gate in self.flat_circuit.gates.iter() {
+            let inputs = &self.flat_circuit.input_ids[gate.inputs.clone()];
+            match gate.gate_type_id {
+                id if id == BoolGateTypeId::Output as u64 => {
+                    wires[gate.gate_id] = wires[inputs[0]];
+                }
+                id if id == BoolGateTypeId::Xor as u64 => {
+                    wires[gate.gate_id] = wires[inputs[0]].xor(wires[inputs[1]]);
+                }
+                id if id == BoolGateTypeId::And as u64 => {
+                    wires[gate.gate_id] = wires[inputs[0]].and(wires[inputs[1]]);
+                }
+                id if id == BoolGateTypeId::Or as u64 => {
+                    wires[gate.gate_id] = wires[inputs[0]].or(wires[inputs[1]]);
+                }
+                id if id == BoolGateTypeId::Not as u64 => {
+                    wires[gate.gate_id] = wires[inputs[0]].not();
+                }
+                id if id == BoolGateTypeId::False as u64 => {
+                    wires[gate.gate_id] = W::zero();
+                }
+                id if id == BoolGateTypeId::True as u64 => {
+                    wires[gate.gate_id] = W::zero().not();
+                }
+                _ => {
+                    let num_output = gate.gate_type.num_output();
+                    let mut outputs = vec![W::zero(); num_output];
+                    for lane in 0..W::NUM_LANES {
+                        gate_input.clear();
+                        gate_input.extend(inputs.iter().map(|id| wires[*id].get_lane(lane)));
+                        let output = gate.gate_type.eval(&gate_input);
+                        debug_assert_eq!(output.len(), num_output);
+                        for (word, bit) in outputs.iter_mut().zip(output) {
+                            word.set_lane(lane, bit);
+                        }
+                    }
+                    wires[gate.gate_id..gate.gate_id + num_output].copy_from_slice(&outputs);
+                }
+            }
+        }
+        self.flat_circuit
+            .output_ids
+            .iter()
+            .map(|id| wires[*id])
+            .collect()
+    }
+
+    /// Evaluates the circuit on every input in `inputs`, packing [`BitSliceWord::NUM_LANES`] inputs per word and processing the packed chunks in parallel.
+    pub fn eval_batch(&self, inputs: &[Vec<bool>]) -> Vec<Vec<bool>> {
+        let num_input = self.circuit.num_input();
+        inputs
+            .par_chunks(W::NUM_LANES)
+            .map_init(Vec::new, |wires, chunk| {
+                let mut input_words = vec![W::zero(); num_input];
+                for (lane, input) in chunk.iter().enumerate() {
+                    debug_assert_eq!(input.len(), num_input);
+                    for (word, bit) in input_words.iter_mut().zip(input.iter()) {
+                        word.set_lane(lane, *bit);
+                    }
+                }
+                let output_words = self.eval_words(&input_words, wires);
+                (0..chunk.len())
+                    .map(|lane| {
+                        output_words
+                            .iter()
+                            .map(|word| word.get_lane(lane))
+                            .collect_vec()
+                    })
+                    .collect_vec()
+            })
+            .flatten()
+            .collect()
+    }
+}
+
+impl BoolCircuit {
+    /// Evaluates the circuit on every input in `inputs` with [`BatchBoolCircuitEvaluator`] over 64 lanes.
+    pub fn eval_batch(&self, inputs: &[Vec<bool>]) -> Vec<Vec<bool>> {
+        BatchBoolCircuitEvaluator::<u64>::new(Arc::new(self.clone())).eval_batch(inputs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::Rng;
+
+    fn random_inputs<R: Rng>(num_inputs: usize, num_input: usize, rng: &mut R) -> Vec<Vec<bool>> {
+        (0..num_inputs)
+            .map(|_| (0..num_input).map(|_| rng.gen_bool(0.5)).collect_vec())
+            .collect_vec()
+    }
+
+    fn test_circuit() -> Arc<BoolCircuit> {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let input = circuit_builder.input(3);
+        let or = circuit_builder.or(&input[0], &input[1]);
+        let and = circuit_builder.and(&input[1], &input[2]);
+        let xor = circuit_builder.xor(&or, &and);
+        let not = circuit_builder.not(&xor);
+        Arc::new(circuit_builder.output(vec![xor, not, and]))
+    }
+
+    fn assert_batch_matches_plain<W: BitSliceWord>(
+        circuit: &Arc<BoolCircuit>,
+        inputs: &[Vec<bool>],
+    ) {
+        let evaluator = BatchBoolCircuitEvaluator::<W>::new(circuit.clone());
+        let outputs = evaluator.eval_batch(inputs);
+        assert_eq!(outputs.len(), inputs.len());
+        let mut plain_evaluator = PlainBoolCircuitEvaluator::new(circuit.clone());
+        for (input, output) in inputs.iter().zip(outputs.iter()) {
+            assert_eq!(*output, plain_evaluator.eval(input));
+        }
+    }
+
+    #[test]
+    fn test_eval_batch_u64() {
+        let mut rng = rand::thread_rng();
+        let circuit = test_circuit();
+        // The last chunk is only partially filled.
+        let inputs = random_inputs(1000, 3, &mut rng);
+        assert_batch_matches_plain::<u64>(&circuit, &inputs);
+        assert_eq!(circuit.eval_batch(&inputs).len(), 1000);
+    }
+
+    #[test]
+    fn test_eval_batch_arrays() {
+        let mut rng = rand::thread_rng();
+        let circuit = test_circuit();
+        let inputs = random_inputs(1000, 3, &mut rng);
+        assert_batch_matches_plain::<[u64; 2]>(&circuit, &inputs);
+        assert_batch_matches_plain::<[u64; 4]>(&circuit, &inputs);
+    }
+
+    #[test]
+    fn test_eval_batch_empty() {
+        let circuit = test_circuit();
+        assert!(circuit.eval_batch(&[]).is_empty());
+    }
+}
@@ -1,4 +1,6 @@
-use crate::circuit::{BoolCircuit, BoolGateType};
+use crate::circuit::{BoolCircuit, BoolGateType, BoolGateTypeRegistry, PlainBoolCircuitEvaluator};
+use std::convert::TryInto;
+use std::sync::Arc;
 
 #[derive(Debug, Clone)]
 pub enum BoolGateTypeId {
@@ -8,15 +10,48 @@ pub enum BoolGateTypeId {
     And = 3,
     Or = 4,
     Not = 5,
+    ChipRef = 6,
+    False = 7,
+    True = 8,
+    Projection = 9,
+    Lut = 10,
 }
 
-#[derive(Debug, Clone)]
+impl BoolGateTypeId {
+    /// Returns the built-in gate type whose id is `gate_type_id`, if any.
+    pub fn from_id(gate_type_id: u64) -> Option<Self> {
+        [
+            Self::Input,
+            Self::Output,
+            Self::Xor,
+            Self::And,
+            Self::Or,
+            Self::Not,
+            Self::ChipRef,
+            Self::False,
+            Self::True,
+            Self::Projection,
+            Self::Lut,
+        ]
+        .into_iter()
+        .find(|id| id.clone() as u64 == gate_type_id)
+    }
+}
+
+/// Returns a new instance of the built-in gate type whose id is `gate_type_id` without parameters.
+/// Use [`crate::circuit::BoolGateTypeRegistry`] for the gate types with parameters or the custom ones.
+pub fn gate_type_from_id(gate_type_id: u64) -> Result<Box<dyn BoolGateType>, crate::Error> {
+    BoolGateTypeRegistry::new().construct(gate_type_id, &[])
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct InputGateType {}
 
 impl BoolGateType for InputGateType {
-    /// [WARNING] An evaluator of [`BoolCircuit`] should not call `eval` function when the gate type is [`InputGateType`].
+    /// Returns the given bits as they are, which a caller should pass as the value of the input.
+    /// The evaluators of [`BoolCircuit`] do not call it because the value is given by the inputs of the circuit, and they return [`crate::Error::UnexpectedGateType`] for an input gate whose id is not that of an input.
     fn eval(&self, input: &[bool]) -> Vec<bool> {
-        unimplemented!("An evaluator of BoolCircuit should not call `eval` function when the gate type is InputGateType")
+        input.to_vec()
     }
 
     fn num_input(&self) -> usize {
@@ -38,7 +73,7 @@ impl InputGateType {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct OutputGateType {}
 
 impl BoolGateType for OutputGateType {
@@ -66,7 +101,7 @@ impl OutputGateType {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct XorGateType {}
 
 impl BoolGateType for XorGateType {
@@ -93,7 +128,7 @@ impl XorGateType {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct AndGateType {}
 
 impl BoolGateType for AndGateType {
@@ -120,7 +155,7 @@ impl AndGateType {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct OrGateType {}
 
 impl BoolGateType for OrGateType {
@@ -147,7 +182,7 @@ impl OrGateType {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct NotGateType {}
 
 impl BoolGateType for NotGateType {
@@ -173,3 +208,235 @@ impl NotGateType {
         Self {}
     }
 }
+
+/// A reference to the `output_idx`-th output of a chip registered by [`crate::circuit::BoolCircuitBuilder::register_chip`].
+/// Its inputs are the inputs of the chip.
+#[derive(Debug, Clone)]
+pub struct ChipRefType {
+    pub chip: Arc<BoolCircuit>,
+    pub output_idx: usize,
+}
+
+impl BoolGateType for ChipRefType {
+    fn eval(&self, input: &[bool]) -> Vec<bool> {
+        let mut evaluator = PlainBoolCircuitEvaluator::new(self.chip.clone());
+        vec![evaluator.eval_output(input, self.output_idx)]
+    }
+
+    fn num_input(&self) -> usize {
+        self.chip.num_input()
+    }
+
+    fn num_output(&self) -> usize {
+        1
+    }
+
+    fn gate_type_id(&self) -> u64 {
+        6
+    }
+}
+
+impl ChipRefType {
+    pub fn new(chip: Arc<BoolCircuit>, output_idx: usize) -> Self {
+        Self { chip, output_idx }
+    }
+}
+
+/// A gate without inputs that always outputs `value`.
+/// Its gate type id is [`BoolGateTypeId::False`] or [`BoolGateTypeId::True`] depending on `value`.
+#[derive(Debug, Clone)]
+pub struct ConstantGateType {
+    pub value: bool,
+}
+
+impl BoolGateType for ConstantGateType {
+    fn eval(&self, _input: &[bool]) -> Vec<bool> {
+        vec![self.value]
+    }
+
+    fn num_input(&self) -> usize {
+        0
+    }
+
+    fn num_output(&self) -> usize {
+        1
+    }
+
+    fn gate_type_id(&self) -> u64 {
+        if self.value {
+            8
+        } else {
+            7
+        }
+    }
+}
+
+impl ConstantGateType {
+    pub fn new(value: bool) -> Self {
+        Self { value }
+    }
+}
+
+/// The `output_idx`-th output of a multi-output gate, which is its only input.
+/// Its gate id is always the gate id of the input plus `output_idx`, so the evaluators do not evaluate it but read the value written by the input gate.
+#[derive(Debug, Clone)]
+pub struct ProjectionGateType {
+    pub output_idx: usize,
+}
+
+impl BoolGateType for ProjectionGateType {
+    /// Returns the given bit as it is, which a caller should pass as the `output_idx`-th output of the input gate.
+    /// The evaluators of [`BoolCircuit`] do not call it because the value is already written at the gate id.
+    fn eval(&self, input: &[bool]) -> Vec<bool> {
+        vec![input[0]]
+    }
+
+    fn num_input(&self) -> usize {
+        1
+    }
+
+    fn num_output(&self) -> usize {
+        1
+    }
+
+    fn gate_type_id(&self) -> u64 {
+        9
+    }
+
+    fn params(&self) -> Vec<u8> {
+        (self.output_idx as u64).to_le_bytes().to_vec()
+    }
+}
+
+impl ProjectionGateType {
+    pub fn new(output_idx: usize) -> Self {
+        Self { output_idx }
+    }
+}
+
+/// A lookup table of `num_input` inputs and `num_output` outputs.
+/// The `row`-th entry of `table` holds the outputs when the `idx`-th input is the `idx`-th bit of `row`, i.e., the inputs are little-endian.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LutGateType {
+    pub num_input: usize,
+    pub num_output: usize,
+    pub table: Vec<Vec<bool>>,
+}
+
+impl BoolGateType for LutGateType {
+    fn eval(&self, input: &[bool]) -> Vec<bool> {
+        let row = input
+            .iter()
+            .enumerate()
+            .fold(0, |acc, (idx, bit)| acc | ((*bit as usize) << idx));
+        self.table[row].clone()
+    }
+
+    fn num_input(&self) -> usize {
+        self.num_input
+    }
+
+    fn num_output(&self) -> usize {
+        self.num_output
+    }
+
+    fn gate_type_id(&self) -> u64 {
+        10
+    }
+
+    /// `num_input` and `num_output` as u64 in little-endian, followed by the bits of `table` packed row by row.
+    fn params(&self) -> Vec<u8> {
+        let mut params = (self.num_input as u64).to_le_bytes().to_vec();
+        params.extend_from_slice(&(self.num_output as u64).to_le_bytes());
+        let bits = self.table.concat();
+        params.extend(bits.chunks(8).map(|chunk| {
+            chunk
+                .iter()
+                .enumerate()
+                .fold(0u8, |acc, (idx, bit)| acc | ((*bit as u8) << idx))
+        }));
+        params
+    }
+}
+
+impl LutGateType {
+    /// The maximum number of inputs, which bounds the size of `table`.
+    pub const MAX_NUM_INPUT: usize = 16;
+
+    /// Returns [`crate::Error::InvalidGateParams`] if `table` does not have `2^num_input` rows of the same non-zero length.
+    pub fn new(num_input: usize, table: Vec<Vec<bool>>) -> Result<Self, crate::Error> {
+        let invalid = |reason: &str| {
+            crate::Error::InvalidGateParams(BoolGateTypeId::Lut as u64, reason.to_string())
+        };
+        if num_input > Self::MAX_NUM_INPUT {
+            return Err(invalid("too many inputs"));
+        }
+        if table.len() != 1 << num_input {
+            return Err(invalid("the table must have 2^num_input rows"));
+        }
+        let num_output = table[0].len();
+        if num_output == 0 || table.iter().any(|row| row.len() != num_output) {
+            return Err(invalid(
+                "every row must have the same non-zero number of outputs",
+            ));
+        }
+        Ok(Self {
+            num_input,
+            num_output,
+            table,
+        })
+    }
+
+    /// Returns the table mapping each `row` to the `num_output` little-endian bits of `f(row)`, e.g., an S-box.
+    pub fn from_fn<F>(num_input: usize, num_output: usize, f: F) -> Result<Self, crate::Error>
+    where
+        F: Fn(usize) -> usize,
+    {
+        let invalid = |reason: &str| {
+            crate::Error::InvalidGateParams(BoolGateTypeId::Lut as u64, reason.to_string())
+        };
+        if num_input > Self::MAX_NUM_INPUT {
+            return Err(invalid("too many inputs"));
+        }
+        if num_output > usize::BITS as usize {
+            return Err(invalid("the outputs must fit in usize"));
+        }
+        let table = (0..1 << num_input)
+            .map(|row| {
+                let value = f(row);
+                (0..num_output).map(|idx| (value >> idx) & 1 == 1).collect()
+            })
+            .collect();
+        Self::new(num_input, table)
+    }
+
+    /// Decodes the parameters returned by [`BoolGateType::params`].
+    pub fn from_params(params: &[u8]) -> Result<Self, crate::Error> {
+        let invalid = |reason: &str| {
+            crate::Error::InvalidGateParams(BoolGateTypeId::Lut as u64, reason.to_string())
+        };
+        if params.len() < 16 {
+            return Err(invalid("the parameters are too short"));
+        }
+        let num_input = u64::from_le_bytes(params[0..8].try_into().unwrap());
+        let num_output = u64::from_le_bytes(params[8..16].try_into().unwrap());
+        if num_input > Self::MAX_NUM_INPUT as u64 || num_output == 0 {
+            return Err(invalid("the shape of the table is invalid"));
+        }
+        let num_input = num_input as usize;
+        let num_output = usize::try_from(num_output)
+            .map_err(|_| invalid("the shape of the table is invalid"))?;
+        let num_bits = num_output
+            .checked_mul(1 << num_input)
+            .ok_or_else(|| invalid("the shape of the table is invalid"))?;
+        let bytes = &params[16..];
+        if bytes.len() != num_bits.div_ceil(8) {
+            return Err(invalid("the length of the table is invalid"));
+        }
+        let bits = (0..num_bits)
+            .map(|idx| (bytes[idx / 8] >> (idx % 8)) & 1 == 1)
+            .collect::<Vec<bool>>();
+        let table = bits.chunks(num_output).map(|row| row.to_vec()).collect();
+        Self::new(num_input, table)
+    }
+}
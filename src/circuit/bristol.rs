@@ -0,0 +1,460 @@
+use super::*;
+use std::path::Path;
+
+impl BoolCircuit {
+    /// Parses a circuit in Bristol Fashion, supporting the XOR, AND, INV, EQ, EQW and MAND gates.
+    /// All the input values are concatenated into the inputs of [`BoolCircuit`], and all the output values into its outputs.
+    pub fn from_bristol(s: &str) -> Result<Self, crate::Error> {
+        let mut lines = s
+            .lines()
+            .enumerate()
+            .map(|(idx, line)| (idx + 1, line.split_whitespace().collect_vec()))
+            .filter(|(_, tokens)| !tokens.is_empty());
+        let mut next_header = |name: &str| {
+            lines.next().ok_or_else(|| {
+                crate::Error::InvalidBristolCircuit(0, format!("the {} line is missing", name))
+            })
+        };
+        let (line_no, header) = next_header("header")?;
+        if header.len() != 2 {
+            return Err(crate::Error::InvalidBristolCircuit(
+                line_no,
+                "the header must consist of the numbers of gates and wires".to_string(),
+            ));
+        }
+        let num_gates = parse_number(header[0], line_no)?;
+        let num_wires = parse_number(header[1], line_no)?;
+        let (line_no, input_line) = next_header("input")?;
+        let num_input = parse_value_sizes(&input_line, line_no)?;
+        let (line_no, output_line) = next_header("output")?;
+        let num_output = parse_value_sizes(&output_line, line_no)?;
+        if num_input.saturating_add(num_output) > num_wires {
+            return Err(crate::Error::InvalidBristolCircuit(
+                line_no,
+                format!(
+                    "{} input wires and {} output wires exceed the number of wires {}",
+                    num_input, num_output, num_wires
+                ),
+            ));
+        }
+
+        // Every wire other than the inputs is assigned by a gate line, each of which has fewer outputs than tokens, so the untrusted `num_wires` is bounded before allocating the wires.
+        let lines = lines.collect_vec();
+        let max_num_wires = num_input
+            + lines
+                .iter()
+                .map(|(_, tokens)| tokens.len().saturating_sub(3))
+                .sum::<usize>();
+        if num_wires > max_num_wires {
+            return Err(crate::Error::InvalidBristolCircuit(
+                0,
+                format!(
+                    "the header declares {} wires, but the inputs and the gates assign at most {} wires",
+                    num_wires, max_num_wires
+                ),
+            ));
+        }
+
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let mut wires = vec![None; num_wires];
+        for (wire, gate) in wires.iter_mut().zip(circuit_builder.input(num_input)) {
+            *wire = Some(gate);
+        }
+        let mut gate_count = 0;
+        for (line_no, tokens) in lines {
+            gate_count += 1;
+            if tokens.len() < 3 {
+                return Err(crate::Error::InvalidBristolCircuit(
+                    line_no,
+                    "a gate must have the numbers of inputs and outputs and its type".to_string(),
+                ));
+            }
+            let gate_num_input = parse_number(tokens[0], line_no)?;
+            let gate_num_output = parse_number(tokens[1], line_no)?;
+            if tokens.len() != 3 + gate_num_input + gate_num_output {
+                return Err(crate::Error::InvalidBristolCircuit(
+                    line_no,
+                    format!(
+                        "the gate must have {} input wires and {} output wires",
+                        gate_num_input, gate_num_output
+                    ),
+                ));
+            }
+            let in_tokens = &tokens[2..2 + gate_num_input];
+            let out_wires = tokens[2 + gate_num_input..2 + gate_num_input + gate_num_output]
+                .iter()
+                .map(|token| {
+                    let wire = parse_number(token, line_no)?;
+                    if wire >= num_wires {
+                        return Err(crate::Error::InvalidBristolCircuit(
+                            line_no,
+                            format!("the wire {} is out of range", wire),
+                        ));
+                    }
+                    Ok(wire)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let gate_type = *tokens.last().unwrap();
+            let expect_arity = |expected_input: usize, expected_output: usize| {
+                if gate_num_input != expected_input || gate_num_output != expected_output {
+                    return Err(crate::Error::InvalidBristolCircuit(
+                        line_no,
+                        format!(
+                            "the {} gate must have {} inputs and {} outputs",
+                            gate_type, expected_input, expected_output
+                        ),
+                    ));
+                }
+                Ok(())
+            };
+            let input_wire = |token: &str| -> Result<Arc<BoolGate>, crate::Error> {
+                let wire = parse_number(token, line_no)?;
+                wires.get(wire).cloned().flatten().ok_or_else(|| {
+                    crate::Error::InvalidBristolCircuit(
+                        line_no,
+                        format!("the wire {} is used before it is assigned", wire),
+                    )
+                })
+            };
+            let outputs = match gate_type {
+                "XOR" | "AND" => {
+                    expect_arity(2, 1)?;
+                    let a = input_wire(in_tokens[0])?;
+                    let b = input_wire(in_tokens[1])?;
+                    if gate_type == "XOR" {
+                        vec![circuit_builder.xor(&a, &b)]
+                    } else {
+                        vec![circuit_builder.and(&a, &b)]
+                    }
+                }
+                "INV" => {
+                    expect_arity(1, 1)?;
+                    vec![circuit_builder.not(&input_wire(in_tokens[0])?)]
+                }
+                "EQW" => {
+                    expect_arity(1, 1)?;
+                    vec![input_wire(in_tokens[0])?]
+                }
+                "EQ" => {
+                    expect_arity(1, 1)?;
+                    let bit = match in_tokens[0] {
+                        "0" => false,
+                        "1" => true,
+                        token => {
+                            return Err(crate::Error::InvalidBristolCircuit(
+                                line_no,
+                                format!("the input {} of the EQ gate is not a constant", token),
+                            ))
+                        }
+                    };
+                    vec![circuit_builder.constant(bit)]
+                }
+                "MAND" => {
+                    if gate_num_input != 2 * gate_num_output {
+                        return Err(crate::Error::InvalidBristolCircuit(
+                            line_no,
+                            "the MAND gate must have twice as many inputs as outputs".to_string(),
+                        ));
+                    }
+                    let inputs = in_tokens
+                        .iter()
+                        .map(|token| input_wire(token))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    (0..gate_num_output)
+                        .map(|idx| {
+                            circuit_builder.and(&inputs[idx], &inputs[gate_num_output + idx])
+                        })
+                        .collect_vec()
+                }
+                _ => {
+                    return Err(crate::Error::InvalidBristolCircuit(
+                        line_no,
+                        format!("the gate type {} is not supported", gate_type),
+                    ))
+                }
+            };
+            for (wire, gate) in out_wires.into_iter().zip(outputs) {
+                wires[wire] = Some(gate);
+            }
+        }
+        if gate_count != num_gates {
+            return Err(crate::Error::InvalidBristolCircuit(
+                0,
+                format!(
+                    "the header declares {} gates, but {} gates are found",
+                    num_gates, gate_count
+                ),
+            ));
+        }
+        let output_gates = wires[num_wires - num_output..]
+            .iter()
+            .enumerate()
+            .map(|(idx, wire)| {
+                wire.clone().ok_or_else(|| {
+                    crate::Error::InvalidBristolCircuit(
+                        0,
+                        format!(
+                            "the output wire {} is never assigned",
+                            num_wires - num_output + idx
+                        ),
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(circuit_builder.output(output_gates))
+    }
+
+    pub fn from_bristol_file<P: AsRef<Path>>(path: P) -> Result<Self, crate::Error> {
+        Self::from_bristol(&std::fs::read_to_string(path)?)
+    }
+
+    /// Writes the circuit in Bristol Fashion with a single input value of [`BoolCircuit::num_input`] bits and a single output value of [`BoolCircuit::num_output`] bits.
+    /// OR gates are written as `XOR(XOR(a, b), AND(a, b))` and every output is copied into the last wires with an EQW gate.
+    /// The chips are inlined by [`BoolCircuit::flatten_chips`] and the lookup tables are lowered by [`BoolCircuit::lower_luts`] in advance.
+    pub fn to_bristol(&self) -> Result<String, crate::Error> {
+        if self.has_chips() {
+            return self.flatten_chips().to_bristol();
+        }
+        if self.has_luts() {
+            return self.lower_luts().to_bristol();
+        }
+        let mut wire_of_gate = HashMap::<GateId, usize>::new();
+        for idx in 0..self.num_input() {
+            wire_of_gate.insert(GateId::new(idx), idx);
+        }
+        let mut num_wires = self.num_input();
+        let mut gate_lines = vec![];
+        let mut output_inputs = vec![];
+        for gate in sort_gates(self) {
+            let gate_type_id = gate.gate_type.gate_type_id();
+            if gate_type_id == BoolGateTypeId::Input as u64 {
+                continue;
+            }
+            let inputs = gate
+                .inputs
+                .iter()
+                .map(|input_gate| wire_of_gate[&input_gate.gate_id])
+                .collect_vec();
+            if gate_type_id == BoolGateTypeId::Output as u64 {
+                output_inputs.push((gate.gate_id, inputs[0]));
+                continue;
+            }
+            let out = num_wires;
+            match gate_type_id {
+                id if id == BoolGateTypeId::Xor as u64 => {
+                    gate_lines.push(format!("2 1 {} {} {} XOR", inputs[0], inputs[1], out));
+                }
+                id if id == BoolGateTypeId::And as u64 => {
+                    gate_lines.push(format!("2 1 {} {} {} AND", inputs[0], inputs[1], out));
+                }
+                id if id == BoolGateTypeId::Not as u64 => {
+                    gate_lines.push(format!("1 1 {} {} INV", inputs[0], out));
+                }
+                id if id == BoolGateTypeId::False as u64 => {
+                    gate_lines.push(format!("1 1 0 {} EQ", out));
+                }
+                id if id == BoolGateTypeId::True as u64 => {
+                    gate_lines.push(format!("1 1 1 {} EQ", out));
+                }
+                id if id == BoolGateTypeId::Or as u64 => {
+                    let (xor, and) = (out, out + 1);
+                    gate_lines.push(format!("2 1 {} {} {} XOR", inputs[0], inputs[1], xor));
+                    gate_lines.push(format!("2 1 {} {} {} AND", inputs[0], inputs[1], and));
+                    gate_lines.push(format!("2 1 {} {} {} XOR", xor, and, out + 2));
+                    num_wires += 2;
+                }
+                _ => return Err(crate::Error::UnsupportedGateType(gate_type_id)),
+            }
+            wire_of_gate.insert(gate.gate_id, num_wires);
+            num_wires += 1;
+        }
+        // The output wires must be the last wires.
+        let output_wire_of_gate = output_inputs
+            .iter()
+            .enumerate()
+            .map(|(idx, (gate_id, _))| (*gate_id, num_wires + idx))
+            .collect::<HashMap<_, _>>();
+        for (gate_id, input) in output_inputs.iter() {
+            gate_lines.push(format!(
+                "1 1 {} {} EQW",
+                input, output_wire_of_gate[gate_id]
+            ));
+        }
+        num_wires += output_inputs.len();
+        // An output gate may appear more than once in `output_gates`, so copy the duplicated ones again.
+        let mut output_wires = self
+            .output_gates
+            .iter()
+            .map(|gate| output_wire_of_gate[&gate.gate_id])
+            .collect_vec();
+        let mut seen = HashSet::new();
+        for wire in output_wires.iter_mut() {
+            if !seen.insert(*wire) {
+                gate_lines.push(format!("1 1 {} {} EQW", wire, num_wires));
+                *wire = num_wires;
+                num_wires += 1;
+            }
+        }
+        debug_assert!(output_wires
+            .iter()
+            .enumerate()
+            .all(|(idx, wire)| *wire == num_wires - output_wires.len() + idx));
+
+        let mut lines = vec![
+            format!("{} {}", gate_lines.len(), num_wires),
+            format!("1 {}", self.num_input()),
+            format!("1 {}", self.num_output()),
+            String::new(),
+        ];
+        lines.append(&mut gate_lines);
+        Ok(lines.join("\n") + "\n")
+    }
+}
+
+fn parse_number(token: &str, line_no: usize) -> Result<usize, crate::Error> {
+    token.parse::<usize>().map_err(|_| {
+        crate::Error::InvalidBristolCircuit(line_no, format!("{} is not a number", token))
+    })
+}
+
+/// Parses the line of the input or output values, returning the total number of the wires.
+fn parse_value_sizes(tokens: &[&str], line_no: usize) -> Result<usize, crate::Error> {
+    let num_values = parse_number(tokens[0], line_no)?;
+    if tokens.len() != num_values + 1 {
+        return Err(crate::Error::InvalidBristolCircuit(
+            line_no,
+            format!("{} value sizes are expected", num_values),
+        ));
+    }
+    tokens[1..].iter().try_fold(0usize, |acc, token| {
+        acc.checked_add(parse_number(token, line_no)?)
+            .ok_or_else(|| {
+                crate::Error::InvalidBristolCircuit(line_no, "too many wires".to_string())
+            })
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_same_outputs(circuit1: &BoolCircuit, circuit2: &BoolCircuit) {
+        assert_eq!(circuit1.num_input(), circuit2.num_input());
+        assert_eq!(circuit1.num_output(), circuit2.num_output());
+        let mut evaluator1 = PlainBoolCircuitEvaluator::new(Arc::new(circuit1.clone()));
+        let mut evaluator2 = PlainBoolCircuitEvaluator::new(Arc::new(circuit2.clone()));
+        for bits in 0..(1 << circuit1.num_input()) {
+            let input = (0..circuit1.num_input())
+                .map(|idx| (bits >> idx) & 1 == 1)
+                .collect_vec();
+            assert_eq!(evaluator1.eval(&input), evaluator2.eval(&input));
+        }
+    }
+
+    #[test]
+    fn test_full_adder() {
+        // Inputs: a = 0, b = 1, carry_in = 2; outputs: sum = 7, carry_out = 8.
+        let bristol = "5 9
+            2 2 1
+            1 2
+
+            2 1 0 1 3 XOR
+            2 1 3 2 7 XOR
+            2 1 0 1 4 AND
+            2 1 3 2 5 AND
+            2 1 4 5 8 XOR
+        ";
+        let circuit = Arc::new(BoolCircuit::from_bristol(bristol).unwrap());
+        assert_eq!(circuit.num_input(), 3);
+        assert_eq!(circuit.num_output(), 2);
+        let mut evaluator = PlainBoolCircuitEvaluator::new(circuit);
+        for bits in 0..8usize {
+            let input = (0..3).map(|idx| (bits >> idx) & 1 == 1).collect_vec();
+            let sum = bits.count_ones();
+            assert_eq!(evaluator.eval(&input), vec![sum & 1 == 1, sum >= 2]);
+        }
+    }
+
+    #[test]
+    fn test_inv_eq_eqw_mand() {
+        let bristol = "5 10
+            1 2
+            1 5
+
+            1 1 0 2 INV
+            1 1 1 5 EQ
+            1 1 0 6 EQ
+            4 2 0 1 1 2 8 9 MAND
+            1 1 1 7 EQW
+        ";
+        let circuit = Arc::new(BoolCircuit::from_bristol(bristol).unwrap());
+        let mut evaluator = PlainBoolCircuitEvaluator::new(circuit);
+        for bits in 0..4usize {
+            let (a, b) = (bits & 1 == 1, bits >> 1 == 1);
+            assert_eq!(evaluator.eval(&[a, b]), vec![true, false, b, a & b, !a & b]);
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let input = circuit_builder.input(3);
+        let or = circuit_builder.or(&input[0], &input[1]);
+        let and = circuit_builder.and(&input[1], &input[2]);
+        let xor = circuit_builder.xor(&or, &and);
+        let not = circuit_builder.not(&xor);
+        let circuit = circuit_builder.output(vec![xor, not.clone(), input[2].clone(), not]);
+        let bristol = circuit.to_bristol().unwrap();
+        let decoded = BoolCircuit::from_bristol(&bristol).unwrap();
+        assert_same_outputs(&circuit, &decoded);
+        assert_eq!(
+            decoded.to_bristol().unwrap().lines().count(),
+            bristol.lines().count()
+        );
+    }
+
+    #[test]
+    fn test_chips_and_luts() {
+        let mut chip_builder = BoolCircuitBuilder::new();
+        let chip_input = chip_builder.input(2);
+        let or = chip_builder.or(&chip_input[0], &chip_input[1]);
+        let not = chip_builder.not(&or);
+        let chip = chip_builder.output(vec![or, not]);
+
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let input = circuit_builder.input(3);
+        let chip = circuit_builder.register_chip(chip);
+        let chip_outputs = circuit_builder.call_chip(&input[0..2], &chip);
+        let mut outputs = circuit_builder
+            .lut_from_fn(&[chip_outputs[1].clone(), input[2].clone()], 2, |row| {
+                row ^ 2
+            })
+            .unwrap();
+        outputs.push(chip_outputs[0].clone());
+        let circuit = circuit_builder.output(outputs);
+        let decoded = BoolCircuit::from_bristol(&circuit.to_bristol().unwrap()).unwrap();
+        assert_same_outputs(&circuit, &decoded);
+    }
+
+    #[test]
+    fn test_invalid_bristol() {
+        let cases = [
+            "",
+            "1 3\n1 1\n1 1\n\n2 1 0 1 2 XOR",
+            "1 3\n1 2\n1 1\n\n2 1 0 1 2 NAND",
+            "1 3\n1 2\n1 1\n\n2 1 0 2 2 AND",
+            "1 3\n1 2\n1 1\n\n1 1 0 1 2 XOR",
+            "2 3\n1 2\n1 1\n\n2 1 0 1 2 XOR",
+            "1 4\n1 2\n1 1\n\n2 1 0 1 2 XOR",
+            "1 3\n1 2\n1 1\n\n1 1 5 2 EQ",
+            "0 18446744073709551615\n1 1\n1 1\n",
+            "0 3\n2 18446744073709551615 1\n1 1\n",
+        ];
+        for case in cases {
+            assert!(matches!(
+                BoolCircuit::from_bristol(case),
+                Err(crate::Error::InvalidBristolCircuit(_, _))
+            ));
+        }
+    }
+}
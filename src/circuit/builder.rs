@@ -1,7 +1,16 @@
+use super::optimize::rebuild_into;
 use super::*;
 pub struct BoolCircuitBuilder {
     pub num_input: usize,
     pub num_gate: usize,
+    /// The cached gates of the constants `false` and `true`.
+    constants: [Option<Arc<BoolGate>>; 2],
+}
+
+impl Default for BoolCircuitBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl BoolCircuitBuilder {
@@ -9,6 +18,7 @@ impl BoolCircuitBuilder {
         Self {
             num_input: 0,
             num_gate: 0,
+            constants: [None, None],
         }
     }
 
@@ -45,31 +55,89 @@ impl BoolCircuitBuilder {
         BoolCircuit::new(output_gates, self.num_input)
     }
 
-    // pub fn register_chip(&mut self, chip: BoolCircuit) -> Arc<BoolCircuit> {
-    //     Arc::new(chip)
-    // }
-
-    // pub fn call_chip(
-    //     &mut self,
-    //     inputs: &[Arc<BoolGate>],
-    //     chip_ref: &Arc<BoolCircuit>,
-    // ) -> Vec<Arc<BoolGate>> {
-    //     debug_assert_eq!(inputs.len(), chip_ref.num_input);
-    //     let num_output = chip_ref.num_output();
-    //     let chip_num_gate = chip_ref.num_gates() - chip_ref.num_input() - num_output;
-    //     let gate_id_offset = GateId::new(self.num_gate + chip_num_gate);
-    //     self.num_gate += chip_num_gate + num_output;
-    //     (0..num_output)
-    //         .map(|idx| {
-    //             let gate_id = gate_id_offset + GateId::new(idx);
-    //             Arc::new(BoolGate::new(
-    //                 gate_id,
-    //                 Box::new(ChipRefType::new(chip_ref.clone(), idx)),
-    //                 inputs.into_iter().map(|gate| gate.clone()).collect_vec(),
-    //             ))
-    //         })
-    //         .collect()
-    // }
+    /// Registers `chip` as a sub-circuit that can be called multiple times with [`BoolCircuitBuilder::call_chip`].
+    pub fn register_chip(&mut self, chip: BoolCircuit) -> Arc<BoolCircuit> {
+        Arc::new(chip)
+    }
+
+    /// Calls the chip on `inputs` and returns one [`ChipRefType`] gate per output of the chip.
+    /// The ids of the gates inside the chip are reserved as well so that the chip can be inlined by [`BoolCircuit::flatten_chips`].
+    pub fn call_chip(
+        &mut self,
+        inputs: &[Arc<BoolGate>],
+        chip_ref: &Arc<BoolCircuit>,
+    ) -> Vec<Arc<BoolGate>> {
+        debug_assert_eq!(inputs.len(), chip_ref.num_input);
+        let num_output = chip_ref.num_output();
+        let chip_num_gate = chip_ref.num_gates() - chip_ref.num_input() - num_output;
+        let gate_id_offset = GateId::new(self.num_gate + chip_num_gate);
+        self.num_gate += chip_num_gate + num_output;
+        (0..num_output)
+            .map(|idx| {
+                let gate_id = gate_id_offset + GateId::new(idx);
+                Arc::new(BoolGate::new(
+                    gate_id,
+                    Box::new(ChipRefType::new(chip_ref.clone(), idx)),
+                    inputs.iter().cloned().collect_vec(),
+                ))
+            })
+            .collect()
+    }
+
+    /// Adds a gate of any single-output or multi-output `gate_type`, reserving one id per output.
+    /// The returned gate refers to the first output; use [`BoolCircuitBuilder::gate_outputs`] to refer to all of them.
+    /// A [`ProjectionGateType`] gate is not assigned a new id but the id of the output it refers to.
+    pub fn gate(
+        &mut self,
+        gate_type: Box<dyn BoolGateType>,
+        inputs: Vec<Arc<BoolGate>>,
+    ) -> Arc<BoolGate> {
+        debug_assert_eq!(inputs.len(), gate_type.num_input());
+        if let Some(projection) = gate_type.downcast_ref::<ProjectionGateType>() {
+            debug_assert!(projection.output_idx > 0);
+            let gate_id = inputs[0].gate_id + GateId::new(projection.output_idx);
+            return Arc::new(BoolGate::new(gate_id, gate_type, inputs));
+        }
+        let gate_id = GateId::new(self.num_gate);
+        self.num_gate += gate_type.num_output();
+        Arc::new(BoolGate::new(gate_id, gate_type, inputs))
+    }
+
+    /// Adds a gate of `gate_type` like [`BoolCircuitBuilder::gate`] and returns one gate per output.
+    pub fn gate_outputs(
+        &mut self,
+        gate_type: Box<dyn BoolGateType>,
+        inputs: Vec<Arc<BoolGate>>,
+    ) -> Vec<Arc<BoolGate>> {
+        let num_output = gate_type.num_output();
+        let gate = self.gate(gate_type, inputs);
+        (0..num_output)
+            .map(|idx| self.project(&gate, idx))
+            .collect()
+    }
+
+    /// Returns the gate referring to the `output_idx`-th output of the multi-output `gate`.
+    pub fn project(&mut self, gate: &Arc<BoolGate>, output_idx: usize) -> Arc<BoolGate> {
+        debug_assert!(output_idx < gate.gate_type.num_output());
+        if output_idx == 0 {
+            return gate.clone();
+        }
+        self.gate(
+            Box::new(ProjectionGateType::new(output_idx)),
+            vec![gate.clone()],
+        )
+    }
+
+    /// Returns the [`ConstantGateType`] gate of `bit`.
+    /// The gate is created only once and reused afterwards.
+    pub fn constant(&mut self, bit: bool) -> Arc<BoolGate> {
+        if let Some(gate) = &self.constants[bit as usize] {
+            return gate.clone();
+        }
+        let gate = self.gate(Box::new(ConstantGateType::new(bit)), vec![]);
+        self.constants[bit as usize] = Some(gate.clone());
+        gate
+    }
 
     pub fn not(&mut self, input: &Arc<BoolGate>) -> Arc<BoolGate> {
         let gate_id = GateId::new(self.num_gate);
@@ -111,3 +179,244 @@ impl BoolCircuitBuilder {
         ))
     }
 }
+
+impl BoolCircuit {
+    /// Returns an equivalent circuit where every chip called by [`BoolCircuitBuilder::call_chip`] is inlined recursively.
+    /// Since [`ChipRefType`] cannot be encoded by its gate type id alone, [`EncodedBoolCircuit::from_circuit`] calls this for a circuit with chips.
+    pub fn flatten_chips(&self) -> BoolCircuit {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let inputs = circuit_builder.input(self.num_input());
+        let outputs = inline_circuit(&mut circuit_builder, self, &inputs);
+        circuit_builder.output(outputs)
+    }
+
+    /// Returns whether the circuit has a [`ChipRefType`] gate reachable from its outputs.
+    pub(crate) fn has_chips(&self) -> bool {
+        sort_gates(self)
+            .iter()
+            .any(|gate| gate.gate_type.gate_type_id() == BoolGateTypeId::ChipRef as u64)
+    }
+}
+
+/// Adds the gates of `circuit` to `circuit_builder` with `inputs` as its inputs, and returns the gates of its outputs.
+fn inline_circuit(
+    circuit_builder: &mut BoolCircuitBuilder,
+    circuit: &BoolCircuit,
+    inputs: &[Arc<BoolGate>],
+) -> Vec<Arc<BoolGate>> {
+    // The outputs of each chip call, keyed by the chip and the ids of its inputs, so that a chip is inlined only once per call.
+    let mut chip_outputs = HashMap::<(*const BoolCircuit, Vec<GateId>), Vec<Arc<BoolGate>>>::new();
+    rebuild_into(
+        circuit_builder,
+        circuit,
+        inputs,
+        |circuit_builder, gate, new_inputs| {
+            let Some(chip_ref) = gate.gate_type.downcast_ref::<ChipRefType>() else {
+                return circuit_builder.gate(gate.gate_type.clone(), new_inputs);
+            };
+            let key = (
+                Arc::as_ptr(&chip_ref.chip),
+                new_inputs.iter().map(|gate| gate.gate_id).collect_vec(),
+            );
+            chip_outputs
+                .entry(key)
+                .or_insert_with(|| inline_circuit(circuit_builder, &chip_ref.chip, &new_inputs))
+                [chip_ref.output_idx]
+                .clone()
+        },
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Returns the chip computing `(a ^ b ^ carry, majority(a, b, carry))`.
+    fn full_adder_chip() -> BoolCircuit {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let input = circuit_builder.input(3);
+        let xor = circuit_builder.xor(&input[0], &input[1]);
+        let sum = circuit_builder.xor(&xor, &input[2]);
+        let and1 = circuit_builder.and(&input[0], &input[1]);
+        let and2 = circuit_builder.and(&xor, &input[2]);
+        let carry = circuit_builder.or(&and1, &and2);
+        circuit_builder.output(vec![sum, carry])
+    }
+
+    /// Builds a `num_bits`-bit ripple-carry adder from `adder_chip`, outputting the sum and the carry.
+    fn ripple_carry_adder(
+        circuit_builder: &mut BoolCircuitBuilder,
+        a: &[Arc<BoolGate>],
+        b: &[Arc<BoolGate>],
+        carry: &Arc<BoolGate>,
+        adder_chip: &Arc<BoolCircuit>,
+    ) -> Vec<Arc<BoolGate>> {
+        let mut carry = carry.clone();
+        let mut outputs = vec![];
+        for (a, b) in a.iter().zip(b.iter()) {
+            let sum_carry =
+                circuit_builder.call_chip(&[a.clone(), b.clone(), carry.clone()], adder_chip);
+            outputs.push(sum_carry[0].clone());
+            carry = sum_carry[1].clone();
+        }
+        outputs.push(carry);
+        outputs
+    }
+
+    fn adder_circuit(num_bits: usize) -> BoolCircuit {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let adder_chip = circuit_builder.register_chip(full_adder_chip());
+        let input = circuit_builder.input(2 * num_bits + 1);
+        let outputs = ripple_carry_adder(
+            &mut circuit_builder,
+            &input[0..num_bits],
+            &input[num_bits..2 * num_bits],
+            &input[2 * num_bits],
+            &adder_chip,
+        );
+        circuit_builder.output(outputs)
+    }
+
+    fn assert_adds(circuit: &Arc<BoolCircuit>, num_bits: usize) {
+        let mut plain_evaluator = PlainBoolCircuitEvaluator::new(circuit.clone());
+        let mut flat_evaluator = FlatBoolCircuitEvaluator::new(circuit.clone());
+        for bits in 0..(1usize << (2 * num_bits + 1)) {
+            let input = (0..2 * num_bits + 1)
+                .map(|idx| (bits >> idx) & 1 == 1)
+                .collect_vec();
+            let a = bits & ((1 << num_bits) - 1);
+            let b = (bits >> num_bits) & ((1 << num_bits) - 1);
+            let sum = a + b + (bits >> (2 * num_bits));
+            let expected = (0..num_bits + 1)
+                .map(|idx| (sum >> idx) & 1 == 1)
+                .collect_vec();
+            assert_eq!(plain_evaluator.eval(&input), expected);
+            assert_eq!(flat_evaluator.eval(&input), expected);
+        }
+    }
+
+    fn assert_no_chips(circuit: &BoolCircuit) {
+        let gates = sort_gates(circuit);
+        assert!(gates
+            .iter()
+            .all(|gate| gate.gate_type.gate_type_id() != BoolGateTypeId::ChipRef as u64));
+        assert_eq!(
+            gates.iter().map(|gate| gate.gate_id).unique().count(),
+            gates.len()
+        );
+    }
+
+    #[test]
+    fn test_call_chip() {
+        let circuit = adder_circuit(4);
+        let chip_num_gate = full_adder_chip().num_gates() - 3 - 2;
+        // 9 inputs, 4 chip calls and 5 outputs.
+        assert_eq!(circuit.num_gates(), 9 + 4 * (chip_num_gate + 2) + 5);
+        assert_adds(&Arc::new(circuit), 4);
+    }
+
+    #[test]
+    fn test_flatten_chips() {
+        let circuit = adder_circuit(4);
+        let flattened = circuit.flatten_chips();
+        assert_no_chips(&flattened);
+        assert_adds(&Arc::new(flattened.clone()), 4);
+        // `to_bristol` flattens the chips by itself.
+        let bristol = flattened.to_bristol().unwrap();
+        assert_eq!(circuit.to_bristol().unwrap(), bristol);
+        let decoded = BoolCircuit::from_bristol(&bristol).unwrap();
+        assert_adds(&Arc::new(decoded), 4);
+    }
+
+    #[test]
+    fn test_nested_chips() {
+        let num_bits = 2;
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let two_bit_adder_chip = circuit_builder.register_chip(adder_circuit(num_bits));
+        // Adds (a + b + carry) + (c + d) with two calls of the same 2-bit adder chip.
+        let input = circuit_builder.input(4 * num_bits + 1);
+        let sum1 = circuit_builder.call_chip(
+            &[&input[0..2 * num_bits], &input[4 * num_bits..]].concat(),
+            &two_bit_adder_chip,
+        );
+        let sum2 = circuit_builder.call_chip(
+            &[&input[2 * num_bits..4 * num_bits], &input[4 * num_bits..]].concat(),
+            &two_bit_adder_chip,
+        );
+        let circuit = circuit_builder.output([sum1, sum2].concat());
+        let flattened = circuit.flatten_chips();
+        assert_no_chips(&flattened);
+        let mut evaluator = PlainBoolCircuitEvaluator::new(Arc::new(circuit));
+        let mut flattened_evaluator = PlainBoolCircuitEvaluator::new(Arc::new(flattened));
+        for bits in 0..(1usize << (4 * num_bits + 1)) {
+            let input = (0..4 * num_bits + 1)
+                .map(|idx| (bits >> idx) & 1 == 1)
+                .collect_vec();
+            assert_eq!(evaluator.eval(&input), flattened_evaluator.eval(&input));
+        }
+    }
+
+    /// A NOT gate counting how many times it is evaluated.
+    #[derive(Debug, Clone)]
+    struct CountingNotGateType {
+        count: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl BoolGateType for CountingNotGateType {
+        fn eval(&self, input: &[bool]) -> Vec<bool> {
+            self.count
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            vec![!input[0]]
+        }
+
+        fn num_input(&self) -> usize {
+            1
+        }
+
+        fn num_output(&self) -> usize {
+            1
+        }
+
+        fn gate_type_id(&self) -> u64 {
+            100
+        }
+    }
+
+    #[test]
+    fn test_chip_evaluated_once_per_call() {
+        let count = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let chip = {
+            let mut chip_builder = BoolCircuitBuilder::new();
+            let input = chip_builder.input(2);
+            let not = chip_builder.gate(
+                Box::new(CountingNotGateType {
+                    count: count.clone(),
+                }),
+                vec![input[0].clone()],
+            );
+            let and = chip_builder.and(&not, &input[1]);
+            let xor = chip_builder.xor(&not, &input[1]);
+            chip_builder.output(vec![not, and, xor])
+        };
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let chip = circuit_builder.register_chip(chip);
+        let input = circuit_builder.input(2);
+        let outputs = circuit_builder.call_chip(&input, &chip);
+        let circuit = Arc::new(circuit_builder.output(outputs));
+        let evaluated_count = |eval: &mut dyn FnMut() -> Vec<bool>| {
+            count.store(0, std::sync::atomic::Ordering::Relaxed);
+            assert_eq!(eval(), vec![false, false, true]);
+            count.load(std::sync::atomic::Ordering::Relaxed)
+        };
+        let mut plain_evaluator = PlainBoolCircuitEvaluator::new(circuit.clone());
+        assert_eq!(
+            evaluated_count(&mut || plain_evaluator.eval(&[true, true])),
+            1
+        );
+        let mut flat_evaluator = FlatBoolCircuitEvaluator::new(circuit);
+        assert_eq!(
+            evaluated_count(&mut || flat_evaluator.eval(&[true, true])),
+            1
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use super::optimize::rebuild_into;
+use super::*;
+
+impl BoolCircuit {
+    /// Returns the circuit that feeds the outputs of `self` into the inputs of `next`.
+    /// It returns [`crate::Error::InvalidInputLength`] if the number of outputs of `self` differs from the number of inputs of `next`.
+    pub fn compose(&self, next: &BoolCircuit) -> Result<BoolCircuit, crate::Error> {
+        if self.num_output() != next.num_input() {
+            return Err(crate::Error::InvalidInputLength(
+                next.num_input(),
+                self.num_output(),
+            ));
+        }
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let inputs = circuit_builder.input(self.num_input());
+        let outputs = append_circuit(&mut circuit_builder, self, &inputs);
+        let outputs = append_circuit(&mut circuit_builder, next, &outputs);
+        Ok(circuit_builder.output(outputs))
+    }
+
+    /// Returns the circuit that evaluates `self` and `other` side by side.
+    /// Its inputs are the inputs of `self` followed by those of `other`, and so are its outputs.
+    pub fn parallel(&self, other: &BoolCircuit) -> BoolCircuit {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let inputs = circuit_builder.input(self.num_input() + other.num_input());
+        let mut outputs = append_circuit(&mut circuit_builder, self, &inputs[0..self.num_input()]);
+        outputs.extend(append_circuit(
+            &mut circuit_builder,
+            other,
+            &inputs[self.num_input()..],
+        ));
+        circuit_builder.output(outputs)
+    }
+
+    /// Returns the circuit whose `idx`-th output is the `output_indices[idx]`-th output of `self`.
+    /// The outputs can be dropped, permuted and duplicated, and the gates no longer reachable from the outputs are removed.
+    pub fn select_outputs(&self, output_indices: &[usize]) -> Result<BoolCircuit, crate::Error> {
+        let output_gates = output_indices
+            .iter()
+            .map(|idx| {
+                self.output_gates
+                    .get(*idx)
+                    .cloned()
+                    .ok_or(crate::Error::OutputIndexOutOfRange(*idx, self.num_output()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let (circuit, _) = BoolCircuit::new(output_gates, self.num_input()).remove_dead_gates();
+        Ok(circuit)
+    }
+
+    /// Specializes the circuit by fixing the `idx`-th input to `fixed_inputs[idx]` and folding the constants.
+    /// The remaining inputs keep their order.
+    pub fn fix_inputs(
+        &self,
+        fixed_inputs: &HashMap<usize, bool>,
+    ) -> Result<BoolCircuit, crate::Error> {
+        if let Some(idx) = fixed_inputs.keys().find(|idx| **idx >= self.num_input()) {
+            return Err(crate::Error::InputIndexOutOfRange(*idx, self.num_input()));
+        }
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let mut free_inputs = circuit_builder
+            .input(self.num_input() - fixed_inputs.len())
+            .into_iter();
+        let inputs = (0..self.num_input())
+            .map(|idx| match fixed_inputs.get(&idx) {
+                Some(bit) => circuit_builder.constant(*bit),
+                None => free_inputs.next().unwrap(),
+            })
+            .collect_vec();
+        let outputs = append_circuit(&mut circuit_builder, self, &inputs);
+        let (circuit, _) = circuit_builder.output(outputs).fold_constants();
+        Ok(circuit)
+    }
+}
+
+/// Adds the gates of `circuit` reachable from its outputs to `circuit_builder` with `inputs` as its inputs, and returns the gates of its outputs.
+/// Unlike the inlining in [`BoolCircuit::flatten_chips`], the chips are kept as they are.
+fn append_circuit(
+    circuit_builder: &mut BoolCircuitBuilder,
+    circuit: &BoolCircuit,
+    inputs: &[Arc<BoolGate>],
+) -> Vec<Arc<BoolGate>> {
+    rebuild_into(
+        circuit_builder,
+        circuit,
+        inputs,
+        |circuit_builder, gate, new_inputs| {
+            circuit_builder.gate(gate.gate_type.clone(), new_inputs)
+        },
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::circuit::optimize::count_gates;
+
+    /// Outputs `(x0 AND x1, x0 XOR x1, NOT x2)`.
+    fn test_circuit() -> BoolCircuit {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let input = circuit_builder.input(3);
+        let and = circuit_builder.and(&input[0], &input[1]);
+        let xor = circuit_builder.xor(&input[0], &input[1]);
+        let not = circuit_builder.not(&input[2]);
+        circuit_builder.output(vec![and, xor, not])
+    }
+
+    fn eval(circuit: BoolCircuit, input: &[bool]) -> Vec<bool> {
+        let mut evaluator = PlainBoolCircuitEvaluator::new(Arc::new(circuit));
+        evaluator.try_eval(input).unwrap()
+    }
+
+    fn all_inputs(num_input: usize) -> Vec<Vec<bool>> {
+        (0..1usize << num_input)
+            .map(|bits| (0..num_input).map(|idx| (bits >> idx) & 1 == 1).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_compose_and_parallel() {
+        let circuit = test_circuit();
+        let composed = circuit.compose(&circuit).unwrap();
+        assert!(composed.validate().is_ok());
+        for input in all_inputs(3) {
+            let expected = eval(test_circuit(), &eval(test_circuit(), &input));
+            assert_eq!(eval(composed.clone(), &input), expected);
+        }
+        let select = circuit.select_outputs(&[0, 1]).unwrap();
+        assert!(matches!(
+            select.compose(&circuit),
+            Err(crate::Error::InvalidInputLength(3, 2))
+        ));
+
+        let parallel = circuit.parallel(&select);
+        assert_eq!(parallel.num_input(), 6);
+        assert_eq!(parallel.num_output(), 5);
+        assert!(parallel.validate().is_ok());
+        for input in all_inputs(6) {
+            let mut expected = eval(test_circuit(), &input[0..3]);
+            expected.extend(eval(select.clone(), &input[3..6]));
+            assert_eq!(eval(parallel.clone(), &input), expected);
+        }
+    }
+
+    #[test]
+    fn test_select_outputs() {
+        let circuit = test_circuit();
+        let selected = circuit.select_outputs(&[2, 0, 2]).unwrap();
+        assert!(selected.validate().is_ok());
+        // The XOR gate is removed.
+        assert_eq!(count_gates(&selected), count_gates(&circuit) - 1);
+        for input in all_inputs(3) {
+            let outputs = eval(test_circuit(), &input);
+            assert_eq!(
+                eval(selected.clone(), &input),
+                vec![outputs[2], outputs[0], outputs[2]]
+            );
+        }
+        assert!(matches!(
+            circuit.select_outputs(&[3]),
+            Err(crate::Error::OutputIndexOutOfRange(3, 3))
+        ));
+    }
+
+    #[test]
+    fn test_fix_inputs() {
+        let circuit = test_circuit();
+        let fixed = circuit
+            .fix_inputs(&HashMap::from([(0, true), (2, false)]))
+            .unwrap();
+        assert_eq!(fixed.num_input(), 1);
+        assert!(fixed.validate().is_ok());
+        for bit in [false, true] {
+            assert_eq!(
+                eval(fixed.clone(), &[bit]),
+                eval(test_circuit(), &[true, bit, false])
+            );
+        }
+        // `x1 AND true` and `x1 XOR true` are folded into `x1` and `NOT x1`.
+        assert!(count_gates(&fixed) < count_gates(&circuit));
+        assert!(matches!(
+            circuit.fix_inputs(&HashMap::from([(3, true)])),
+            Err(crate::Error::InputIndexOutOfRange(3, 3))
+        ));
+    }
+}
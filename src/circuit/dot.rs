@@ -0,0 +1,133 @@
+use super::*;
+use petgraph::graph::{DiGraph, NodeIndex};
+use std::fmt::Write;
+
+/// A node of the graph returned by [`BoolCircuit::to_graph`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GateNode {
+    pub gate_id: GateId,
+    pub gate_type_id: u64,
+}
+
+impl BoolCircuit {
+    /// Converts the circuit into a graph with an edge from every gate to each gate taking it as an input.
+    /// The weight of each edge is the index of the input, and the nodes are added in a topological order.
+    pub fn to_graph(&self) -> DiGraph<GateNode, usize> {
+        let mut graph = DiGraph::new();
+        let mut nodes = HashMap::<GateId, NodeIndex>::new();
+        for gate in sort_gates(self) {
+            let node = graph.add_node(GateNode {
+                gate_id: gate.gate_id,
+                gate_type_id: gate.gate_type.gate_type_id(),
+            });
+            for (idx, input_gate) in gate.inputs.iter().enumerate() {
+                graph.add_edge(nodes[&input_gate.gate_id], node, idx);
+            }
+            nodes.insert(gate.gate_id, node);
+        }
+        graph
+    }
+
+    /// Exports the circuit in the DOT language of Graphviz, labelling each node with its gate type and [`GateId`].
+    pub fn to_dot(&self) -> String {
+        self.dot_string(None)
+    }
+
+    /// Exports the circuit in the DOT language like [`BoolCircuit::to_dot`], labelling each wire with its value in `wires`, e.g., [`PlainBoolCircuitEvaluator::wires`] after an evaluation.
+    pub fn to_dot_with_values(&self, wires: &HashMap<GateId, bool>) -> String {
+        self.dot_string(Some(wires))
+    }
+
+    fn dot_string(&self, wires: Option<&HashMap<GateId, bool>>) -> String {
+        let mut dot = String::from("digraph BoolCircuit {\n");
+        for gate in sort_gates(self) {
+            let gate_type_id = gate.gate_type.gate_type_id();
+            let type_name = match BoolGateTypeId::from_id(gate_type_id) {
+                Some(id) => format!("{:?}", id),
+                None => format!("Type{}", gate_type_id),
+            };
+            let style = if gate_type_id == BoolGateTypeId::Input as u64 {
+                ", shape=box, style=filled, fillcolor=lightblue"
+            } else if gate_type_id == BoolGateTypeId::Output as u64 {
+                ", shape=box, style=filled, fillcolor=lightpink"
+            } else {
+                ""
+            };
+            writeln!(
+                dot,
+                "  g{} [label=\"{} {}\"{}];",
+                gate.gate_id.id, type_name, gate.gate_id.id, style
+            )
+            .unwrap();
+            for input_gate in gate.inputs.iter() {
+                match wires.and_then(|wires| wires.get(&input_gate.gate_id)) {
+                    Some(value) => writeln!(
+                        dot,
+                        "  g{} -> g{} [label=\"{}\"];",
+                        input_gate.gate_id.id, gate.gate_id.id, *value as u8
+                    ),
+                    None => writeln!(dot, "  g{} -> g{};", input_gate.gate_id.id, gate.gate_id.id),
+                }
+                .unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use petgraph::algo::toposort;
+
+    fn test_circuit() -> BoolCircuit {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let input = circuit_builder.input(2);
+        let xor = circuit_builder.xor(&input[0], &input[1]);
+        let not = circuit_builder.not(&xor);
+        circuit_builder.output(vec![not])
+    }
+
+    #[test]
+    fn test_to_graph() {
+        let graph = test_circuit().to_graph();
+        assert_eq!(graph.node_count(), 5);
+        assert_eq!(graph.edge_count(), 4);
+        let sorted = toposort(&graph, None).unwrap();
+        assert_eq!(graph[sorted[4]].gate_type_id, BoolGateTypeId::Output as u64);
+        let xor = graph
+            .node_indices()
+            .find(|node| graph[*node].gate_id == GateId::new(2))
+            .unwrap();
+        assert_eq!(
+            graph
+                .edges_directed(xor, petgraph::Direction::Incoming)
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let circuit = test_circuit();
+        let dot = circuit.to_dot();
+        assert!(dot.starts_with("digraph BoolCircuit {\n"));
+        assert!(dot
+            .contains("  g0 [label=\"Input 0\", shape=box, style=filled, fillcolor=lightblue];\n"));
+        assert!(dot.contains("  g2 [label=\"Xor 2\"];\n"));
+        assert!(dot.contains(
+            "  g4 [label=\"Output 4\", shape=box, style=filled, fillcolor=lightpink];\n"
+        ));
+        assert!(dot.contains("  g2 -> g3;\n"));
+        assert!(dot.ends_with("}\n"));
+
+        let circuit = Arc::new(circuit);
+        let mut evaluator = PlainBoolCircuitEvaluator::new(circuit.clone());
+        evaluator.eval(&[true, false]);
+        let dot = circuit.to_dot_with_values(&evaluator.wires);
+        assert!(dot.contains("  g0 -> g2 [label=\"1\"];\n"));
+        assert!(dot.contains("  g1 -> g2 [label=\"0\"];\n"));
+        assert!(dot.contains("  g3 -> g4 [label=\"0\"];\n"));
+    }
+}
@@ -1,10 +1,12 @@
 use super::*;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct EncodedBoolGate {
     pub gate_id: GateId,
     pub gate_type_id: u64,
     pub inputs: Vec<GateId>,
+    /// The parameters returned by [`BoolGateType::params`].
+    pub params: Vec<u8>,
 }
 
 impl EncodedBoolGate {
@@ -13,6 +15,7 @@ impl EncodedBoolGate {
             gate_id: gate.gate_id,
             gate_type_id: gate.gate_type.gate_type_id(),
             inputs: gate.inputs.iter().map(|gate| gate.gate_id).collect(),
+            params: gate.gate_type.params(),
         }
     }
 
@@ -20,7 +23,327 @@ impl EncodedBoolGate {
         bincode::serialize(self).unwrap()
     }
 
-    pub fn from_bytes(bytes: &[u8]) -> Self {
-        bincode::deserialize(bytes).expect("The bytes of EncodedBoolGate is invalid.")
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, crate::Error> {
+        Ok(bincode::deserialize(bytes)?)
+    }
+}
+
+/// A flat encoding of [`BoolCircuit`], where the gates refer to their inputs by [`GateId`] instead of [`Arc`].
+/// `gates` starts with all the input gates and is sorted topologically.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncodedBoolCircuit {
+    pub num_input: usize,
+    pub gates: Vec<EncodedBoolGate>,
+    pub output_gates: Vec<GateId>,
+}
+
+impl EncodedBoolCircuit {
+    /// Encodes `circuit`, whose chips are inlined by [`BoolCircuit::flatten_chips`] in advance because a [`ChipRefType`] gate cannot be decoded.
+    pub fn from_circuit(circuit: &BoolCircuit) -> Self {
+        if circuit.has_chips() {
+            return Self::from_circuit(&circuit.flatten_chips());
+        }
+        let input_gates = (0..circuit.num_input()).map(|idx| EncodedBoolGate {
+            gate_id: GateId::new(idx),
+            gate_type_id: BoolGateTypeId::Input as u64,
+            inputs: vec![],
+            params: vec![],
+        });
+        let other_gates = sort_gates(circuit)
+            .into_iter()
+            .filter(|gate| gate.gate_type.gate_type_id() != BoolGateTypeId::Input as u64)
+            .map(|gate| EncodedBoolGate::from_gate(&gate));
+        Self {
+            num_input: circuit.num_input(),
+            gates: input_gates.chain(other_gates).collect(),
+            output_gates: circuit
+                .output_gates
+                .iter()
+                .map(|gate| gate.gate_id)
+                .collect(),
+        }
+    }
+
+    /// Rebuilds the [`BoolCircuit`] with the built-in gate types.
+    pub fn to_circuit(&self) -> Result<BoolCircuit, crate::Error> {
+        self.to_circuit_with_registry(&BoolGateTypeRegistry::new())
+    }
+
+    /// Rebuilds the [`BoolCircuit`] by re-linking the gates and constructing every [`BoolGateType`] from its `gate_type_id` and `params` with `registry`.
+    /// The rebuilt circuit is checked by [`BoolCircuit::validate`], and its error is returned as [`crate::Error::InvalidEncodedCircuit`].
+    pub fn to_circuit_with_registry(
+        &self,
+        registry: &BoolGateTypeRegistry,
+    ) -> Result<BoolCircuit, crate::Error> {
+        let mut gates = HashMap::<GateId, Arc<BoolGate>>::new();
+        for encoded_gate in self.gates.iter() {
+            let gate_type = registry.construct(encoded_gate.gate_type_id, &encoded_gate.params)?;
+            if gate_type.num_input() != encoded_gate.inputs.len() {
+                return Err(crate::Error::InvalidEncodedCircuit(format!(
+                    "the gate {} has {} inputs, but its gate type {} expects {}",
+                    encoded_gate.gate_id.id,
+                    encoded_gate.inputs.len(),
+                    encoded_gate.gate_type_id,
+                    gate_type.num_input()
+                )));
+            }
+            let is_input = encoded_gate.gate_type_id == BoolGateTypeId::Input as u64;
+            if is_input != (encoded_gate.gate_id.id < self.num_input) {
+                return Err(crate::Error::InvalidEncodedCircuit(format!(
+                    "the gate {} must be an input gate if and only if its id is less than {}",
+                    encoded_gate.gate_id.id, self.num_input
+                )));
+            }
+            let inputs = encoded_gate
+                .inputs
+                .iter()
+                .map(|input_id| {
+                    gates.get(input_id).cloned().ok_or_else(|| {
+                        crate::Error::InvalidEncodedCircuit(format!(
+                            "the gate {} refers to the gate {}, which is not defined before it",
+                            encoded_gate.gate_id.id, input_id.id
+                        ))
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let gate = Arc::new(BoolGate::new(encoded_gate.gate_id, gate_type, inputs));
+            if gates.insert(encoded_gate.gate_id, gate).is_some() {
+                return Err(crate::Error::InvalidEncodedCircuit(format!(
+                    "the gate {} is defined more than once",
+                    encoded_gate.gate_id.id
+                )));
+            }
+        }
+        for idx in 0..self.num_input {
+            if !gates.contains_key(&GateId::new(idx)) {
+                return Err(crate::Error::InvalidEncodedCircuit(format!(
+                    "the input gate {} is not defined",
+                    idx
+                )));
+            }
+        }
+        let output_gates = self
+            .output_gates
+            .iter()
+            .map(|output_id| {
+                gates.get(output_id).cloned().ok_or_else(|| {
+                    crate::Error::InvalidEncodedCircuit(format!(
+                        "the output gate {} is not defined",
+                        output_id.id
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let circuit = BoolCircuit::new(output_gates, self.num_input);
+        circuit
+            .validate()
+            .map_err(|err| crate::Error::InvalidEncodedCircuit(err.to_string()))?;
+        Ok(circuit)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, crate::Error> {
+        Ok(bincode::deserialize(bytes)?)
+    }
+}
+
+impl BoolCircuit {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        EncodedBoolCircuit::from_circuit(self).to_bytes()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, crate::Error> {
+        EncodedBoolCircuit::from_bytes(bytes)?.to_circuit()
+    }
+
+    pub fn from_bytes_with_registry(
+        bytes: &[u8],
+        registry: &BoolGateTypeRegistry,
+    ) -> Result<Self, crate::Error> {
+        EncodedBoolCircuit::from_bytes(bytes)?.to_circuit_with_registry(registry)
+    }
+
+    pub fn to_json(&self) -> Result<String, crate::Error> {
+        Ok(serde_json::to_string(&EncodedBoolCircuit::from_circuit(
+            self,
+        ))?)
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, crate::Error> {
+        Self::from_json_with_registry(s, &BoolGateTypeRegistry::new())
+    }
+
+    pub fn from_json_with_registry(
+        s: &str,
+        registry: &BoolGateTypeRegistry,
+    ) -> Result<Self, crate::Error> {
+        let encoded: EncodedBoolCircuit = serde_json::from_str(s)?;
+        encoded.to_circuit_with_registry(registry)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn test_circuit() -> BoolCircuit {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let input = circuit_builder.input(4);
+        let or = circuit_builder.or(&input[0], &input[1]);
+        let and = circuit_builder.and(&input[1], &input[2]);
+        let xor = circuit_builder.xor(&or, &and);
+        let not = circuit_builder.not(&xor);
+        circuit_builder.output(vec![xor, not, and])
+    }
+
+    fn assert_same_circuit(circuit1: &BoolCircuit, circuit2: &BoolCircuit) {
+        assert_eq!(
+            EncodedBoolCircuit::from_circuit(circuit1),
+            EncodedBoolCircuit::from_circuit(circuit2)
+        );
+        assert_eq!(circuit1.num_input(), circuit2.num_input());
+        assert_eq!(circuit1.num_gates(), circuit2.num_gates());
+        let mut evaluator1 = PlainBoolCircuitEvaluator::new(Arc::new(circuit1.clone()));
+        let mut evaluator2 = PlainBoolCircuitEvaluator::new(Arc::new(circuit2.clone()));
+        for bits in 0..(1 << circuit1.num_input()) {
+            let input = (0..circuit1.num_input())
+                .map(|idx| (bits >> idx) & 1 == 1)
+                .collect_vec();
+            assert_eq!(evaluator1.eval(&input), evaluator2.eval(&input));
+        }
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let circuit = test_circuit();
+        let decoded = BoolCircuit::from_bytes(&circuit.to_bytes()).unwrap();
+        assert_same_circuit(&circuit, &decoded);
+        // The unused input gate 3 is also kept.
+        assert_eq!(
+            EncodedBoolCircuit::from_circuit(&decoded).gates.len(),
+            4 + 4 + 3
+        );
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let circuit = test_circuit();
+        let decoded = BoolCircuit::from_json(&circuit.to_json().unwrap()).unwrap();
+        assert_same_circuit(&circuit, &decoded);
+    }
+
+    #[test]
+    fn test_chip_round_trip() {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let chip = circuit_builder.register_chip(test_circuit());
+        let input = circuit_builder.input(5);
+        let outputs = circuit_builder.call_chip(&input[0..4], &chip);
+        let outputs = circuit_builder.call_chip(
+            &[
+                outputs[0].clone(),
+                outputs[2].clone(),
+                input[4].clone(),
+                outputs[1].clone(),
+            ],
+            &chip,
+        );
+        let circuit = circuit_builder.output(outputs);
+        let decoded = BoolCircuit::from_bytes(&circuit.to_bytes()).unwrap();
+        let decoded_json = BoolCircuit::from_json(&circuit.to_json().unwrap()).unwrap();
+        let encoded = EncodedBoolCircuit::from_circuit(&circuit);
+        assert_eq!(EncodedBoolCircuit::from_circuit(&decoded), encoded);
+        assert_eq!(EncodedBoolCircuit::from_circuit(&decoded_json), encoded);
+        let mut evaluator = PlainBoolCircuitEvaluator::new(Arc::new(circuit));
+        let mut decoded_evaluator = PlainBoolCircuitEvaluator::new(Arc::new(decoded));
+        for bits in 0..32usize {
+            let input = (0..5).map(|idx| (bits >> idx) & 1 == 1).collect_vec();
+            assert_eq!(decoded_evaluator.eval(&input), evaluator.eval(&input));
+        }
+    }
+
+    #[test]
+    fn test_gate_bytes_round_trip() {
+        let circuit = test_circuit();
+        for gate in EncodedBoolCircuit::from_circuit(&circuit).gates {
+            assert_eq!(EncodedBoolGate::from_bytes(&gate.to_bytes()).unwrap(), gate);
+        }
+        assert!(matches!(
+            EncodedBoolGate::from_bytes(&[1, 2, 3]),
+            Err(crate::Error::BincodeError(_))
+        ));
+    }
+
+    #[test]
+    fn test_malformed_circuits() {
+        let encoded = EncodedBoolCircuit::from_circuit(&test_circuit());
+        assert!(matches!(
+            BoolCircuit::from_bytes(&encoded.to_bytes()[..10]),
+            Err(crate::Error::BincodeError(_))
+        ));
+        assert!(matches!(
+            BoolCircuit::from_json("{\"num_input\": 1}"),
+            Err(crate::Error::SerdeJsonError(_))
+        ));
+
+        let mut unknown_type = encoded.clone();
+        unknown_type.gates[4].gate_type_id = 100;
+        assert!(matches!(
+            unknown_type.to_circuit(),
+            Err(crate::Error::UnsupportedGateType(100))
+        ));
+
+        let mut wrong_arity = encoded.clone();
+        wrong_arity.gates[4].inputs.pop();
+        assert!(matches!(
+            wrong_arity.to_circuit(),
+            Err(crate::Error::InvalidEncodedCircuit(_))
+        ));
+
+        let mut undefined_input = encoded.clone();
+        undefined_input.gates[4].inputs[0] = GateId::new(100);
+        assert!(matches!(
+            undefined_input.to_circuit(),
+            Err(crate::Error::InvalidEncodedCircuit(_))
+        ));
+
+        let mut duplicated = encoded.clone();
+        duplicated.gates.push(duplicated.gates[5].clone());
+        assert!(matches!(
+            duplicated.to_circuit(),
+            Err(crate::Error::InvalidEncodedCircuit(_))
+        ));
+
+        // The projection at the id of the second output of a lookup table must refer to that output.
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let input = circuit_builder.input(2);
+        let outputs = circuit_builder
+            .lut_from_fn(&input, 2, |row| row ^ 1)
+            .unwrap();
+        let lut_encoded = EncodedBoolCircuit::from_circuit(&circuit_builder.output(outputs));
+        assert!(lut_encoded.to_circuit().is_ok());
+        let mut bad_projection = lut_encoded.clone();
+        let projection = bad_projection
+            .gates
+            .iter_mut()
+            .find(|gate| {
+                gate.gate_type_id == BoolGateTypeId::Projection as u64 && gate.params[0] == 1
+            })
+            .unwrap();
+        projection.params = 0u64.to_le_bytes().to_vec();
+        let err = bad_projection.to_circuit().unwrap_err();
+        assert!(
+            matches!(&err, crate::Error::InvalidEncodedCircuit(reason) if reason.contains("cannot have the gate type id 9")),
+            "{err}"
+        );
+
+        let mut undefined_output = encoded.clone();
+        undefined_output.output_gates.push(GateId::new(100));
+        assert!(matches!(
+            undefined_output.to_circuit(),
+            Err(crate::Error::InvalidEncodedCircuit(_))
+        ));
     }
 }
@@ -3,6 +3,12 @@ use super::*;
 pub struct PlainBoolCircuitEvaluator {
     pub circuit: Arc<BoolCircuit>,
     pub wires: HashMap<GateId, bool>,
+    /// The gates reachable from the outputs sorted by [`sort_gates`] in the first evaluation.
+    sorted_gates: Option<Vec<Arc<BoolGate>>>,
+    /// Whether [`BoolCircuit::validate`] succeeded in the first call of [`PlainBoolCircuitEvaluator::try_eval`].
+    validated: bool,
+    /// The outputs of the chips evaluated in the current evaluation, keyed by the address of the chip and its input bits, so that a chip is evaluated once per call site rather than once per [`ChipRefType`] gate.
+    chip_outputs: HashMap<(usize, Vec<bool>), Vec<bool>>,
 }
 
 impl PlainBoolCircuitEvaluator {
@@ -10,44 +16,361 @@ impl PlainBoolCircuitEvaluator {
         Self {
             circuit,
             wires: HashMap::new(),
+            sorted_gates: None,
+            validated: false,
+            chip_outputs: HashMap::new(),
         }
     }
 
+    /// Evaluates the circuit like [`PlainBoolCircuitEvaluator::eval`], but returns an error instead of panicking if the length of `input` is wrong or the circuit is invalid.
+    /// The circuit is validated by [`BoolCircuit::validate`] only in the first call.
+    pub fn try_eval(&mut self, input: &[bool]) -> Result<Vec<bool>, crate::Error> {
+        check_input_length(&self.circuit, input)?;
+        if !self.validated {
+            self.circuit.validate()?;
+            self.validated = true;
+        }
+        let sorted_gates = self
+            .sorted_gates
+            .take()
+            .unwrap_or_else(|| sort_gates(&self.circuit));
+        let result = self.eval_gates(input, &sorted_gates);
+        self.sorted_gates = Some(sorted_gates);
+        result?;
+        Ok(self
+            .circuit
+            .output_gates
+            .iter()
+            .map(|gate| self.wires[&gate.gate_id])
+            .collect())
+    }
+
+    /// Evaluates the circuit and fills `wires` with the value of every gate reachable from the outputs.
+    /// The gates are evaluated iteratively in a topological order, so that a deep circuit does not overflow the stack.
+    /// It panics if the circuit is invalid, e.g., an input gate is not an input of the circuit; use [`PlainBoolCircuitEvaluator::try_eval`] to get an error instead.
     pub fn eval(&mut self, input: &[bool]) -> Vec<bool> {
         debug_assert_eq!(input.len(), self.circuit.num_input);
+        let sorted_gates = self
+            .sorted_gates
+            .take()
+            .unwrap_or_else(|| sort_gates(&self.circuit));
+        let result = self.eval_gates(input, &sorted_gates);
+        self.sorted_gates = Some(sorted_gates);
+        if let Err(err) = result {
+            panic!("{}", err);
+        }
+        self.circuit
+            .output_gates
+            .iter()
+            .map(|gate| self.wires[&gate.gate_id])
+            .collect()
+    }
+
+    /// Evaluates only the gates that the `output_idx`-th output depends on.
+    pub fn eval_output(&mut self, input: &[bool], output_idx: usize) -> bool {
+        debug_assert_eq!(input.len(), self.circuit.num_input);
+        let output_gate = self.circuit.output_gates[output_idx].clone();
+        let sorted_gates = sort_gates(&BoolCircuit::new(
+            vec![output_gate.clone()],
+            self.circuit.num_input,
+        ));
+        if let Err(err) = self.eval_gates(input, &sorted_gates) {
+            panic!("{}", err);
+        }
+        self.wires[&output_gate.gate_id]
+    }
+
+    /// Evaluates `sorted_gates`, each of which appears after its inputs, into `wires`.
+    /// It returns [`crate::Error::UnexpectedGateType`] for an input gate that is not an input of the circuit.
+    fn eval_gates(
+        &mut self,
+        input: &[bool],
+        sorted_gates: &[Arc<BoolGate>],
+    ) -> Result<(), crate::Error> {
         self.wires.clear();
+        self.chip_outputs.clear();
         for (idx, input) in input.iter().enumerate() {
             self.wires.insert(GateId::new(idx), *input);
         }
-        for idx in 0..self.circuit.num_output() {
-            let output_gate = self.circuit.output_gates[idx].clone();
-            self.eval_gate(&output_gate);
+        for gate in sorted_gates {
+            let gate_type_id = gate.gate_type.gate_type_id();
+            if gate_type_id == BoolGateTypeId::Input as u64 {
+                if !self.wires.contains_key(&gate.gate_id) {
+                    return Err(crate::Error::UnexpectedGateType(
+                        gate.gate_id.id,
+                        gate_type_id,
+                    ));
+                }
+                continue;
+            }
+            // The value of a projection is written when its input gate is evaluated.
+            if gate_type_id == BoolGateTypeId::Projection as u64 {
+                continue;
+            }
+            let input = gate
+                .inputs
+                .iter()
+                .map(|input_gate| self.wires[&input_gate.gate_id])
+                .collect_vec();
+            let output = if let Some(chip_ref) = gate.gate_type.downcast_ref::<ChipRefType>() {
+                let chip = &chip_ref.chip;
+                let chip_outputs = self
+                    .chip_outputs
+                    .entry((Arc::as_ptr(chip) as usize, input))
+                    .or_insert_with_key(|(_, input)| {
+                        PlainBoolCircuitEvaluator::new(chip.clone()).eval(input)
+                    });
+                vec![chip_outputs[chip_ref.output_idx]]
+            } else {
+                gate.gate_type.eval(&input)
+            };
+            debug_assert_eq!(output.len(), gate.gate_type.num_output());
+            for idx in 0..output.len() {
+                self.wires
+                    .insert(gate.gate_id + GateId::new(idx), output[idx]);
+            }
         }
-        self.circuit
+        Ok(())
+    }
+}
+
+/// An evaluator of [`BoolCircuit`] that sorts the gates topologically only once in [`FlatBoolCircuitEvaluator::new`] and then evaluates them iteratively into a flat vector of wires indexed by [`GateId`].
+/// Unlike [`PlainBoolCircuitEvaluator`], it does not recurse, so it can evaluate circuits of any depth, and it can be reused across many inputs.
+pub struct FlatBoolCircuitEvaluator {
+    pub circuit: Arc<BoolCircuit>,
+    pub wires: Vec<bool>,
+    flat_circuit: FlatCircuit,
+}
+
+/// The gates of [`BoolCircuit`] except for the input and projection gates, sorted topologically and referring to their inputs by the ids of the wires.
+pub(crate) struct FlatCircuit {
+    pub(crate) gates: Vec<FlatGate>,
+    pub(crate) input_ids: Vec<usize>,
+    pub(crate) output_ids: Vec<usize>,
+    pub(crate) num_wires: usize,
+}
+
+pub(crate) struct FlatGate {
+    pub(crate) gate_type: Box<dyn BoolGateType>,
+    pub(crate) gate_type_id: u64,
+    pub(crate) gate_id: usize,
+    /// The range of the ids of the input gates in `input_ids`.
+    pub(crate) inputs: std::ops::Range<usize>,
+}
+
+impl FlatCircuit {
+    /// The chips are inlined by [`BoolCircuit::flatten_chips`] in advance, so that a chip is evaluated once per call site rather than once per [`ChipRefType`] gate, and the wires are indexed by the ids of the flattened circuit.
+    pub(crate) fn new(circuit: &BoolCircuit) -> Self {
+        if circuit.has_chips() {
+            return Self::new(&circuit.flatten_chips());
+        }
+        let mut gates = vec![];
+        let mut input_ids = vec![];
+        let mut num_wires = circuit.num_gates().max(circuit.num_input());
+        for gate in sort_gates(circuit) {
+            let gate_type_id = gate.gate_type.gate_type_id();
+            num_wires = num_wires.max(gate.gate_id.id + gate.gate_type.num_output());
+            if gate_type_id == BoolGateTypeId::Input as u64
+                || gate_type_id == BoolGateTypeId::Projection as u64
+            {
+                continue;
+            }
+            let start = input_ids.len();
+            input_ids.extend(gate.inputs.iter().map(|input_gate| input_gate.gate_id.id));
+            gates.push(FlatGate {
+                gate_type: gate.gate_type.clone(),
+                gate_type_id,
+                gate_id: gate.gate_id.id,
+                inputs: start..input_ids.len(),
+            });
+        }
+        let output_ids = circuit
             .output_gates
             .iter()
-            .map(|gate| self.wires[&gate.gate_id])
+            .map(|gate| gate.gate_id.id)
+            .collect();
+        Self {
+            gates,
+            input_ids,
+            output_ids,
+            num_wires,
+        }
+    }
+}
+
+impl FlatBoolCircuitEvaluator {
+    pub fn new(circuit: Arc<BoolCircuit>) -> Self {
+        let flat_circuit = FlatCircuit::new(&circuit);
+        Self {
+            circuit,
+            wires: vec![false; flat_circuit.num_wires],
+            flat_circuit,
+        }
+    }
+
+    /// Validates the circuit by [`BoolCircuit::validate`] before creating the evaluator.
+    pub fn try_new(circuit: Arc<BoolCircuit>) -> Result<Self, crate::Error> {
+        circuit.validate()?;
+        Ok(Self::new(circuit))
+    }
+
+    /// Evaluates the circuit like [`FlatBoolCircuitEvaluator::eval`], but returns an error instead of panicking if the length of `input` is wrong.
+    pub fn try_eval(&mut self, input: &[bool]) -> Result<Vec<bool>, crate::Error> {
+        check_input_length(&self.circuit, input)?;
+        Ok(self.eval(input))
+    }
+
+    pub fn eval(&mut self, input: &[bool]) -> Vec<bool> {
+        debug_assert_eq!(input.len(), self.circuit.num_input);
+        self.wires[..input.len()].copy_from_slice(input);
+        let mut gate_input = vec![];
+        for gate in self.flat_circuit.gates.iter() {
+            let inputs = &self.flat_circuit.input_ids[gate.inputs.clone()];
+            let wires = &mut self.wires;
+            match gate.gate_type_id {
+                id if id == BoolGateTypeId::Output as u64 => {
+                    wires[gate.gate_id] = wires[inputs[0]];
+                }
+                id if id == BoolGateTypeId::Xor as u64 => {
+                    wires[gate.gate_id] = wires[inputs[0]] ^ wires[inputs[1]];
+                }
+                id if id == BoolGateTypeId::And as u64 => {
+                    wires[gate.gate_id] = wires[inputs[0]] & wires[inputs[1]];
+                }
+                id if id == BoolGateTypeId::Or as u64 => {
+                    wires[gate.gate_id] = wires[inputs[0]] | wires[inputs[1]];
+                }
+                id if id == BoolGateTypeId::Not as u64 => {
+                    wires[gate.gate_id] = !wires[inputs[0]];
+                }
+                id if id == BoolGateTypeId::False as u64 => {
+                    wires[gate.gate_id] = false;
+                }
+                id if id == BoolGateTypeId::True as u64 => {
+                    wires[gate.gate_id] = true;
+                }
+                _ => {
+                    gate_input.clear();
+                    gate_input.extend(inputs.iter().map(|id| wires[*id]));
+                    let output = gate.gate_type.eval(&gate_input);
+                    debug_assert_eq!(output.len(), gate.gate_type.num_output());
+                    wires[gate.gate_id..gate.gate_id + output.len()].copy_from_slice(&output);
+                }
+            }
+        }
+        self.flat_circuit
+            .output_ids
+            .iter()
+            .map(|id| self.wires[*id])
             .collect()
     }
+}
+
+fn check_input_length(circuit: &BoolCircuit, input: &[bool]) -> Result<(), crate::Error> {
+    if input.len() != circuit.num_input() {
+        return Err(crate::Error::InvalidInputLength(
+            circuit.num_input(),
+            input.len(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_std::{end_timer, start_timer};
+    use rand::Rng;
+
+    /// Builds a layered circuit of `num_layers` layers of `width` gates, each of which takes two random gates of the previous layer.
+    fn random_layered_circuit<R: Rng>(
+        width: usize,
+        num_layers: usize,
+        rng: &mut R,
+    ) -> Arc<BoolCircuit> {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let mut layer = circuit_builder.input(width);
+        for _ in 0..num_layers {
+            layer = (0..width)
+                .map(|_| {
+                    let a = &layer[rng.gen_range(0..width)];
+                    let b = &layer[rng.gen_range(0..width)];
+                    match rng.gen_range(0..4) {
+                        0 => circuit_builder.xor(a, b),
+                        1 => circuit_builder.and(a, b),
+                        2 => circuit_builder.or(a, b),
+                        _ => circuit_builder.not(a),
+                    }
+                })
+                .collect_vec();
+        }
+        Arc::new(circuit_builder.output(layer))
+    }
+
+    #[test]
+    fn test_flat_evaluator_matches_plain() {
+        let mut rng = rand::thread_rng();
+        let circuit = random_layered_circuit(16, 16, &mut rng);
+        let mut plain_evaluator = PlainBoolCircuitEvaluator::new(circuit.clone());
+        let mut flat_evaluator = FlatBoolCircuitEvaluator::new(circuit.clone());
+        for _ in 0..32 {
+            let input = (0..16).map(|_| rng.gen_bool(0.5)).collect_vec();
+            assert_eq!(flat_evaluator.eval(&input), plain_evaluator.eval(&input));
+        }
+    }
+
+    #[test]
+    fn test_flat_evaluator_deep_circuit() {
+        let depth = 1_000_000;
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let input = circuit_builder.input(2);
+        let mut gate = input[0].clone();
+        for idx in 0..depth {
+            gate = if idx % 2 == 0 {
+                circuit_builder.not(&gate)
+            } else {
+                circuit_builder.xor(&gate, &input[1])
+            };
+        }
+        let circuit = Arc::new(circuit_builder.output(vec![gate]));
+        let mut evaluator = FlatBoolCircuitEvaluator::new(circuit.clone());
+        // Half of the gates are NOTs and the other half XORs with `input[1]`.
+        assert_eq!(evaluator.eval(&[false, false]), vec![false]);
+        assert_eq!(evaluator.eval(&[true, false]), vec![true]);
+        assert_eq!(evaluator.eval(&[false, true]), vec![false]);
+        // The plain evaluator does not recurse either, and `try_eval` fills the wires like `eval`.
+        let mut plain_evaluator = PlainBoolCircuitEvaluator::new(circuit);
+        assert_eq!(plain_evaluator.try_eval(&[true, true]).unwrap(), vec![true]);
+        assert_eq!(plain_evaluator.wires.len(), depth + 3);
+        assert_eq!(plain_evaluator.eval(&[false, true]), vec![false]);
+    }
+
+    #[test]
+    #[ignore]
+    fn bench_flat_evaluator() {
+        let mut rng = rand::thread_rng();
+        let (width, num_layers, num_inputs) = (1000, 1000, 10);
+        let circuit = random_layered_circuit(width, num_layers, &mut rng);
+        println!("num_gates: {}", circuit.num_gates());
+        let inputs = (0..num_inputs)
+            .map(|_| (0..width).map(|_| rng.gen_bool(0.5)).collect_vec())
+            .collect_vec();
 
-    fn eval_gate(&mut self, gate: &BoolGate) {
-        let input = gate
-            .inputs
+        let plain_timer = start_timer!(|| "plain evaluator");
+        let mut plain_evaluator = PlainBoolCircuitEvaluator::new(circuit.clone());
+        let plain_outputs = inputs
             .iter()
-            .map(|input_gate| {
-                if let Some(wire) = self.wires.get(&input_gate.gate_id) {
-                    *wire
-                } else {
-                    self.eval_gate(input_gate);
-                    self.wires[&input_gate.gate_id]
-                }
-            })
+            .map(|input| plain_evaluator.eval(input))
             .collect_vec();
-        let output = gate.gate_type.eval(&input);
-        debug_assert_eq!(output.len(), gate.gate_type.num_output());
-        for idx in 0..output.len() {
-            self.wires
-                .insert(gate.gate_id + GateId::new(idx), output[idx]);
-        }
+        end_timer!(plain_timer);
+
+        let flat_timer = start_timer!(|| "flat evaluator");
+        let mut flat_evaluator = FlatBoolCircuitEvaluator::new(circuit.clone());
+        let flat_outputs = inputs
+            .iter()
+            .map(|input| flat_evaluator.eval(input))
+            .collect_vec();
+        end_timer!(flat_timer);
+        assert_eq!(plain_outputs, flat_outputs);
     }
 }
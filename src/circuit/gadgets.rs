@@ -0,0 +1,359 @@
+use super::*;
+
+/// Word-level gadgets over n-bit words, each of which is a slice of gates in little-endian order, i.e., the least significant bit first.
+/// All arithmetic is unsigned and wraps around modulo `2^n`.
+impl BoolCircuitBuilder {
+    /// Returns the `num_bits`-bit word of the constant `value`.
+    pub fn constant_word(&mut self, value: u64, num_bits: usize) -> Vec<Arc<BoolGate>> {
+        (0..num_bits)
+            .map(|idx| self.constant(idx < 64 && (value >> idx) & 1 == 1))
+            .collect()
+    }
+
+    pub fn not_word(&mut self, a: &[Arc<BoolGate>]) -> Vec<Arc<BoolGate>> {
+        a.iter().map(|a| self.not(a)).collect()
+    }
+
+    pub fn xor_word(&mut self, a: &[Arc<BoolGate>], b: &[Arc<BoolGate>]) -> Vec<Arc<BoolGate>> {
+        debug_assert_eq!(a.len(), b.len());
+        a.iter()
+            .zip(b.iter())
+            .map(|(a, b)| self.xor(a, b))
+            .collect()
+    }
+
+    pub fn and_word(&mut self, a: &[Arc<BoolGate>], b: &[Arc<BoolGate>]) -> Vec<Arc<BoolGate>> {
+        debug_assert_eq!(a.len(), b.len());
+        a.iter()
+            .zip(b.iter())
+            .map(|(a, b)| self.and(a, b))
+            .collect()
+    }
+
+    pub fn or_word(&mut self, a: &[Arc<BoolGate>], b: &[Arc<BoolGate>]) -> Vec<Arc<BoolGate>> {
+        debug_assert_eq!(a.len(), b.len());
+        a.iter().zip(b.iter()).map(|(a, b)| self.or(a, b)).collect()
+    }
+
+    /// Returns `a + b + carry` and the carry out of the most significant bit.
+    /// The full adders use only XOR and AND gates.
+    pub fn add_word(
+        &mut self,
+        a: &[Arc<BoolGate>],
+        b: &[Arc<BoolGate>],
+        carry: Option<&Arc<BoolGate>>,
+    ) -> (Vec<Arc<BoolGate>>, Arc<BoolGate>) {
+        debug_assert_eq!(a.len(), b.len());
+        let mut carry = match carry {
+            Some(carry) => carry.clone(),
+            None => self.constant(false),
+        };
+        let mut sum = vec![];
+        for (a, b) in a.iter().zip(b.iter()) {
+            let xor = self.xor(a, b);
+            sum.push(self.xor(&xor, &carry));
+            // `a & b` and `(a ^ b) & carry` are never true at the same time.
+            let and1 = self.and(a, b);
+            let and2 = self.and(&xor, &carry);
+            carry = self.xor(&and1, &and2);
+        }
+        (sum, carry)
+    }
+
+    /// Returns `a - b` and the borrow out of the most significant bit, which is true if and only if `a < b`.
+    pub fn sub_word(
+        &mut self,
+        a: &[Arc<BoolGate>],
+        b: &[Arc<BoolGate>],
+    ) -> (Vec<Arc<BoolGate>>, Arc<BoolGate>) {
+        debug_assert_eq!(a.len(), b.len());
+        let mut borrow: Option<Arc<BoolGate>> = None;
+        let mut diff = vec![];
+        for (a, b) in a.iter().zip(b.iter()) {
+            let xor = self.xor(a, b);
+            let not_a = self.not(a);
+            let and = self.and(&not_a, b);
+            match borrow {
+                None => {
+                    diff.push(xor);
+                    borrow = Some(and);
+                }
+                Some(prev_borrow) => {
+                    diff.push(self.xor(&xor, &prev_borrow));
+                    // `!a & b` and `!(a ^ b) & borrow` are never true at the same time.
+                    let not_xor = self.not(&xor);
+                    let and2 = self.and(&not_xor, &prev_borrow);
+                    borrow = Some(self.xor(&and, &and2));
+                }
+            }
+        }
+        let borrow = match borrow {
+            Some(borrow) => borrow,
+            None => self.constant(false),
+        };
+        (diff, borrow)
+    }
+
+    /// Returns whether `a == b`.
+    pub fn eq_word(&mut self, a: &[Arc<BoolGate>], b: &[Arc<BoolGate>]) -> Arc<BoolGate> {
+        debug_assert_eq!(a.len(), b.len());
+        let eqs = a
+            .iter()
+            .zip(b.iter())
+            .map(|(a, b)| {
+                let xor = self.xor(a, b);
+                self.not(&xor)
+            })
+            .collect_vec();
+        match eqs.split_first() {
+            Some((first, rest)) => rest
+                .iter()
+                .fold(first.clone(), |acc, eq| self.and(&acc, eq)),
+            None => self.constant(true),
+        }
+    }
+
+    /// Returns whether `a < b`.
+    pub fn lt_word(&mut self, a: &[Arc<BoolGate>], b: &[Arc<BoolGate>]) -> Arc<BoolGate> {
+        self.sub_word(a, b).1
+    }
+
+    /// Returns `if sel { when_true } else { when_false }`, computed as `when_false ^ (sel & (when_false ^ when_true))`.
+    pub fn mux(
+        &mut self,
+        sel: &Arc<BoolGate>,
+        when_false: &Arc<BoolGate>,
+        when_true: &Arc<BoolGate>,
+    ) -> Arc<BoolGate> {
+        let xor = self.xor(when_false, when_true);
+        let and = self.and(sel, &xor);
+        self.xor(when_false, &and)
+    }
+
+    pub fn mux_word(
+        &mut self,
+        sel: &Arc<BoolGate>,
+        when_false: &[Arc<BoolGate>],
+        when_true: &[Arc<BoolGate>],
+    ) -> Vec<Arc<BoolGate>> {
+        debug_assert_eq!(when_false.len(), when_true.len());
+        when_false
+            .iter()
+            .zip(when_true.iter())
+            .map(|(when_false, when_true)| self.mux(sel, when_false, when_true))
+            .collect()
+    }
+
+    /// Returns `a << shift`, filling the lower bits with zeros.
+    pub fn shl_word(&mut self, a: &[Arc<BoolGate>], shift: usize) -> Vec<Arc<BoolGate>> {
+        let shift = shift.min(a.len());
+        let zeros = vec![self.constant(false); shift];
+        [&zeros[..], &a[..a.len() - shift]].concat()
+    }
+
+    /// Returns `a >> shift`, filling the upper bits with zeros.
+    pub fn shr_word(&mut self, a: &[Arc<BoolGate>], shift: usize) -> Vec<Arc<BoolGate>> {
+        let shift = shift.min(a.len());
+        let zeros = vec![self.constant(false); shift];
+        [&a[shift..], &zeros[..]].concat()
+    }
+
+    /// Rotates `a` to the left by `shift` bits. It adds no gates.
+    pub fn rotl_word(&mut self, a: &[Arc<BoolGate>], shift: usize) -> Vec<Arc<BoolGate>> {
+        if a.is_empty() {
+            return vec![];
+        }
+        let mut rotated = a.to_vec();
+        rotated.rotate_right(shift % a.len());
+        rotated
+    }
+
+    /// Rotates `a` to the right by `shift` bits. It adds no gates.
+    pub fn rotr_word(&mut self, a: &[Arc<BoolGate>], shift: usize) -> Vec<Arc<BoolGate>> {
+        if a.is_empty() {
+            return vec![];
+        }
+        let mut rotated = a.to_vec();
+        rotated.rotate_left(shift % a.len());
+        rotated
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::Rng;
+
+    const NUM_BITS: usize = 8;
+
+    fn to_bits(value: u64, num_bits: usize) -> Vec<bool> {
+        (0..num_bits).map(|idx| (value >> idx) & 1 == 1).collect()
+    }
+
+    fn from_bits(bits: &[bool]) -> u64 {
+        bits.iter()
+            .enumerate()
+            .fold(0, |acc, (idx, bit)| acc | ((*bit as u64) << idx))
+    }
+
+    /// Builds a circuit over the inputs `a`, `b` and `sel`, whose outputs are the concatenation of the words returned by `f`.
+    fn word_circuit<F>(f: F) -> (Arc<BoolCircuit>, Vec<usize>)
+    where
+        F: Fn(
+            &mut BoolCircuitBuilder,
+            &[Arc<BoolGate>],
+            &[Arc<BoolGate>],
+            &Arc<BoolGate>,
+        ) -> Vec<Vec<Arc<BoolGate>>>,
+    {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let input = circuit_builder.input(2 * NUM_BITS + 1);
+        let words = f(
+            &mut circuit_builder,
+            &input[0..NUM_BITS],
+            &input[NUM_BITS..2 * NUM_BITS],
+            &input[2 * NUM_BITS],
+        );
+        let word_lens = words.iter().map(|word| word.len()).collect_vec();
+        let circuit = circuit_builder.output(words.concat());
+        (Arc::new(circuit), word_lens)
+    }
+
+    /// Evaluates the circuit on random `(a, b, sel)` and compares each output word with `expected`.
+    fn assert_words<F>(circuit: &Arc<BoolCircuit>, word_lens: &[usize], expected: F)
+    where
+        F: Fn(u64, u64, bool) -> Vec<u64>,
+    {
+        let mut rng = rand::thread_rng();
+        let mut evaluator = PlainBoolCircuitEvaluator::new(circuit.clone());
+        let mut cases = vec![(0, 0), (255, 255), (0, 255), (255, 0), (128, 127)];
+        cases.extend((0..200).map(|_| (rng.gen_range(0..256), rng.gen_range(0..256))));
+        for (a, b) in cases {
+            for sel in [false, true] {
+                let input = [to_bits(a, NUM_BITS), to_bits(b, NUM_BITS), vec![sel]].concat();
+                let output = evaluator.eval(&input);
+                let mut offset = 0;
+                for (word_len, expected) in word_lens.iter().zip(expected(a, b, sel)) {
+                    assert_eq!(
+                        from_bits(&output[offset..offset + word_len]),
+                        expected,
+                        "a = {}, b = {}, sel = {}",
+                        a,
+                        b,
+                        sel
+                    );
+                    offset += word_len;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_constant_word() {
+        let (circuit, word_lens) = word_circuit(|circuit_builder, _, _, _| {
+            vec![
+                circuit_builder.constant_word(0xa5, NUM_BITS),
+                circuit_builder.constant_word(0, NUM_BITS),
+                circuit_builder.constant_word(u64::MAX, NUM_BITS),
+            ]
+        });
+        assert_words(&circuit, &word_lens, |_, _, _| vec![0xa5, 0, 0xff]);
+    }
+
+    #[test]
+    fn test_bitwise_words() {
+        let (circuit, word_lens) = word_circuit(|circuit_builder, a, b, _| {
+            vec![
+                circuit_builder.not_word(a),
+                circuit_builder.xor_word(a, b),
+                circuit_builder.and_word(a, b),
+                circuit_builder.or_word(a, b),
+            ]
+        });
+        assert_words(&circuit, &word_lens, |a, b, _| {
+            vec![!a & 0xff, a ^ b, a & b, a | b]
+        });
+    }
+
+    #[test]
+    fn test_add_sub_word() {
+        let (circuit, word_lens) = word_circuit(|circuit_builder, a, b, sel| {
+            let (sum, carry) = circuit_builder.add_word(a, b, None);
+            let (sum_with_carry, carry2) = circuit_builder.add_word(a, b, Some(sel));
+            let (diff, borrow) = circuit_builder.sub_word(a, b);
+            vec![
+                sum,
+                vec![carry],
+                sum_with_carry,
+                vec![carry2],
+                diff,
+                vec![borrow],
+            ]
+        });
+        assert_words(&circuit, &word_lens, |a, b, sel| {
+            let sum = a + b;
+            let sum_with_carry = a + b + sel as u64;
+            vec![
+                sum & 0xff,
+                sum >> 8,
+                sum_with_carry & 0xff,
+                sum_with_carry >> 8,
+                a.wrapping_sub(b) & 0xff,
+                (a < b) as u64,
+            ]
+        });
+    }
+
+    #[test]
+    fn test_compare_and_mux_word() {
+        let (circuit, word_lens) = word_circuit(|circuit_builder, a, b, sel| {
+            let eq = circuit_builder.eq_word(a, b);
+            let eq_self = circuit_builder.eq_word(a, a);
+            let lt = circuit_builder.lt_word(a, b);
+            let gt = circuit_builder.lt_word(b, a);
+            let mux = circuit_builder.mux_word(sel, a, b);
+            vec![vec![eq], vec![eq_self], vec![lt], vec![gt], mux]
+        });
+        assert_words(&circuit, &word_lens, |a, b, sel| {
+            vec![
+                (a == b) as u64,
+                1,
+                (a < b) as u64,
+                (a > b) as u64,
+                if sel { b } else { a },
+            ]
+        });
+    }
+
+    #[test]
+    fn test_shift_and_rotate_word() {
+        let shifts = [0, 1, 3, 7, 8, 9];
+        let (circuit, word_lens) = word_circuit(|circuit_builder, a, _, _| {
+            shifts
+                .iter()
+                .flat_map(|shift| {
+                    vec![
+                        circuit_builder.shl_word(a, *shift),
+                        circuit_builder.shr_word(a, *shift),
+                        circuit_builder.rotl_word(a, *shift),
+                        circuit_builder.rotr_word(a, *shift),
+                    ]
+                })
+                .collect()
+        });
+        assert_words(&circuit, &word_lens, |a, _, _| {
+            let a = a as u8;
+            shifts
+                .iter()
+                .flat_map(|shift| {
+                    vec![
+                        a.checked_shl(*shift as u32).unwrap_or(0) as u64,
+                        a.checked_shr(*shift as u32).unwrap_or(0) as u64,
+                        a.rotate_left(*shift as u32) as u64,
+                        a.rotate_right(*shift as u32) as u64,
+                    ]
+                })
+                .collect()
+        });
+    }
+}
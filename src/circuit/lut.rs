@@ -0,0 +1,214 @@
+use super::optimize::rebuild;
+use super::*;
+
+impl BoolCircuitBuilder {
+    /// Adds a [`LutGateType`] gate taking `inputs` and returns one gate per output.
+    pub fn lut(&mut self, lut: LutGateType, inputs: &[Arc<BoolGate>]) -> Vec<Arc<BoolGate>> {
+        self.gate_outputs(Box::new(lut), inputs.to_vec())
+    }
+
+    /// Adds the lookup table mapping the little-endian `inputs` to the `num_output` little-endian bits of `f(inputs)`.
+    pub fn lut_from_fn<F>(
+        &mut self,
+        inputs: &[Arc<BoolGate>],
+        num_output: usize,
+        f: F,
+    ) -> Result<Vec<Arc<BoolGate>>, crate::Error>
+    where
+        F: Fn(usize) -> usize,
+    {
+        let lut = LutGateType::from_fn(inputs.len(), num_output, f)?;
+        Ok(self.lut(lut, inputs))
+    }
+
+    /// Builds the outputs of `lut` from XOR/AND/NOT gates.
+    /// Each output is expanded into its algebraic normal form, a XOR of ANDs of the inputs, and the ANDs are shared among the outputs.
+    pub fn lut_with_basic_gates(
+        &mut self,
+        lut: &LutGateType,
+        inputs: &[Arc<BoolGate>],
+    ) -> Vec<Arc<BoolGate>> {
+        debug_assert_eq!(inputs.len(), lut.num_input);
+        let num_rows = 1usize << lut.num_input;
+        let mut monomials = HashMap::<usize, Arc<BoolGate>>::new();
+        (0..lut.num_output)
+            .map(|output_idx| {
+                // The Möbius transform turns the truth table into the coefficients of the monomials.
+                let mut coeffs = lut.table.iter().map(|row| row[output_idx]).collect_vec();
+                for idx in 0..lut.num_input {
+                    for mask in 0..num_rows {
+                        if mask & (1 << idx) != 0 {
+                            coeffs[mask] ^= coeffs[mask ^ (1 << idx)];
+                        }
+                    }
+                }
+                let mut terms = (1..num_rows)
+                    .filter(|mask| coeffs[*mask])
+                    .map(|mask| self.monomial(mask, inputs, &mut monomials))
+                    .collect_vec();
+                if terms.is_empty() {
+                    return self.constant(coeffs[0]);
+                }
+                while terms.len() > 1 {
+                    terms = terms
+                        .chunks(2)
+                        .map(|pair| match pair {
+                            [a, b] => self.xor(a, b),
+                            _ => pair[0].clone(),
+                        })
+                        .collect();
+                }
+                if coeffs[0] {
+                    self.not(&terms[0])
+                } else {
+                    terms[0].clone()
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the AND of the inputs whose indices are the bits of `mask`.
+    fn monomial(
+        &mut self,
+        mask: usize,
+        inputs: &[Arc<BoolGate>],
+        monomials: &mut HashMap<usize, Arc<BoolGate>>,
+    ) -> Arc<BoolGate> {
+        if mask.is_power_of_two() {
+            return inputs[mask.trailing_zeros() as usize].clone();
+        }
+        if let Some(gate) = monomials.get(&mask) {
+            return gate.clone();
+        }
+        let highest = 1 << (usize::BITS - 1 - mask.leading_zeros());
+        let rest = self.monomial(mask ^ highest, inputs, monomials);
+        let gate = self.and(&rest, &inputs[highest.trailing_zeros() as usize]);
+        monomials.insert(mask, gate.clone());
+        gate
+    }
+}
+
+impl BoolCircuit {
+    /// Replaces every [`LutGateType`] gate with XOR/AND/NOT gates by [`BoolCircuitBuilder::lut_with_basic_gates`].
+    pub fn lower_luts(&self) -> BoolCircuit {
+        let mut lowered = HashMap::<GateId, Vec<Arc<BoolGate>>>::new();
+        rebuild(self, |circuit_builder, gate, new_inputs| {
+            if let Some(lut) = gate.gate_type.downcast_ref::<LutGateType>() {
+                let outputs = circuit_builder.lut_with_basic_gates(lut, &new_inputs);
+                let first = outputs[0].clone();
+                lowered.insert(gate.gate_id, outputs);
+                return first;
+            }
+            if let Some(projection) = gate.gate_type.downcast_ref::<ProjectionGateType>() {
+                if let Some(outputs) = lowered.get(&gate.inputs[0].gate_id) {
+                    return outputs[projection.output_idx].clone();
+                }
+            }
+            circuit_builder.gate(gate.gate_type.clone(), new_inputs)
+        })
+    }
+
+    /// Returns whether the circuit has a [`LutGateType`] gate reachable from its outputs.
+    pub(crate) fn has_luts(&self) -> bool {
+        sort_gates(self)
+            .iter()
+            .any(|gate| gate.gate_type.gate_type_id() == BoolGateTypeId::Lut as u64)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// The 4-bit S-box of PRESENT.
+    const SBOX: [usize; 16] = [
+        0xC, 0x5, 0x6, 0xB, 0x9, 0x0, 0xA, 0xD, 0x3, 0xE, 0xF, 0x8, 0x4, 0x7, 0x1, 0x2,
+    ];
+
+    fn sbox_circuit() -> BoolCircuit {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let input = circuit_builder.input(5);
+        let mut outputs = circuit_builder
+            .lut_from_fn(&input[0..4], 4, |row| SBOX[row])
+            .unwrap();
+        // A 2-input table with a constant term: NAND.
+        let nand =
+            LutGateType::new(2, vec![vec![true], vec![true], vec![true], vec![false]]).unwrap();
+        let nand = circuit_builder.lut(nand, &[outputs[3].clone(), input[4].clone()]);
+        outputs.extend(nand);
+        circuit_builder.output(outputs)
+    }
+
+    fn expected_outputs(input: &[bool]) -> Vec<bool> {
+        let row = (0..4).fold(0, |acc, idx| acc | ((input[idx] as usize) << idx));
+        let mut outputs = (0..4).map(|idx| (SBOX[row] >> idx) & 1 == 1).collect_vec();
+        outputs.push(!(outputs[3] && input[4]));
+        outputs
+    }
+
+    #[test]
+    fn test_lut_eval_and_lowering() {
+        let circuit = sbox_circuit();
+        let lowered = circuit.lower_luts();
+        assert!(lowered.stats().gate_counts.keys().all(|gate_type_id| [
+            BoolGateTypeId::Input as u64,
+            BoolGateTypeId::Output as u64,
+            BoolGateTypeId::Xor as u64,
+            BoolGateTypeId::And as u64,
+            BoolGateTypeId::Not as u64,
+        ]
+        .contains(gate_type_id)));
+        let mut evaluator = PlainBoolCircuitEvaluator::new(Arc::new(circuit));
+        let mut lowered_evaluator = PlainBoolCircuitEvaluator::new(Arc::new(lowered));
+        for bits in 0..32usize {
+            let input = (0..5).map(|idx| (bits >> idx) & 1 == 1).collect_vec();
+            let expected = expected_outputs(&input);
+            assert_eq!(evaluator.eval(&input), expected);
+            assert_eq!(lowered_evaluator.eval(&input), expected);
+        }
+    }
+
+    #[test]
+    fn test_lut_encoding() {
+        let circuit = sbox_circuit();
+        let decoded = BoolCircuit::from_bytes(&circuit.to_bytes()).unwrap();
+        assert_eq!(decoded.digest(), circuit.digest());
+        let decoded_json = BoolCircuit::from_json(&circuit.to_json().unwrap()).unwrap();
+        assert_eq!(decoded_json.digest(), circuit.digest());
+        let lut = LutGateType::from_fn(4, 4, |row| SBOX[row]).unwrap();
+        assert_eq!(LutGateType::from_params(&lut.params()).unwrap(), lut);
+    }
+
+    #[test]
+    fn test_invalid_lut() {
+        assert!(matches!(
+            LutGateType::new(2, vec![vec![true]; 3]),
+            Err(crate::Error::InvalidGateParams(10, _))
+        ));
+        assert!(matches!(
+            LutGateType::new(1, vec![vec![true], vec![true, false]]),
+            Err(crate::Error::InvalidGateParams(10, _))
+        ));
+        assert!(matches!(
+            LutGateType::from_fn(LutGateType::MAX_NUM_INPUT + 1, 1, |row| row),
+            Err(crate::Error::InvalidGateParams(10, _))
+        ));
+        let lut = LutGateType::from_fn(3, 2, |row| row).unwrap();
+        let params = lut.params();
+        assert!(matches!(
+            LutGateType::from_params(&params[..params.len() - 1]),
+            Err(crate::Error::InvalidGateParams(10, _))
+        ));
+        assert!(matches!(
+            LutGateType::from_fn(2, usize::BITS as usize + 1, |row| row),
+            Err(crate::Error::InvalidGateParams(10, _))
+        ));
+        // `2^16 * num_output` overflows.
+        let mut params = 16u64.to_le_bytes().to_vec();
+        params.extend(u64::MAX.to_le_bytes());
+        assert!(matches!(
+            LutGateType::from_params(&params),
+            Err(crate::Error::InvalidGateParams(10, _))
+        ));
+    }
+}
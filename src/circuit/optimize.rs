@@ -0,0 +1,622 @@
+use super::*;
+use rand::Rng;
+use std::fmt;
+
+/// The numbers of gates before and after an optimization pass, counting every gate reachable from the outputs except for the input and output gates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GateCountReport {
+    pub num_gates_before: usize,
+    pub num_gates_after: usize,
+}
+
+impl GateCountReport {
+    pub fn new(before: &BoolCircuit, after: &BoolCircuit) -> Self {
+        Self {
+            num_gates_before: count_gates(before),
+            num_gates_after: count_gates(after),
+        }
+    }
+
+    pub fn num_removed_gates(&self) -> usize {
+        self.num_gates_before.saturating_sub(self.num_gates_after)
+    }
+}
+
+impl fmt::Display for GateCountReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} gates -> {} gates ({} removed)",
+            self.num_gates_before,
+            self.num_gates_after,
+            self.num_removed_gates()
+        )
+    }
+}
+
+/// Returns the number of the gates reachable from the outputs of `circuit` except for the input and output gates.
+pub fn count_gates(circuit: &BoolCircuit) -> usize {
+    sort_gates(circuit)
+        .iter()
+        .filter(|gate| {
+            let gate_type_id = gate.gate_type.gate_type_id();
+            gate_type_id != BoolGateTypeId::Input as u64
+                && gate_type_id != BoolGateTypeId::Output as u64
+        })
+        .count()
+}
+
+/// The value of a gate during constant folding.
+#[derive(Clone)]
+enum Folded {
+    Constant(bool),
+    Gate(Arc<BoolGate>),
+}
+
+impl Folded {
+    fn to_gate(&self, circuit_builder: &mut BoolCircuitBuilder) -> Arc<BoolGate> {
+        match self {
+            Folded::Constant(bit) => circuit_builder.constant(*bit),
+            Folded::Gate(gate) => gate.clone(),
+        }
+    }
+}
+
+impl BoolCircuit {
+    /// Returns an equivalent circuit where every gate reachable from the outputs is renumbered densely in a topological order and the unreachable ones are removed.
+    /// All the input gates are kept even if they are unused.
+    pub fn remove_dead_gates(&self) -> (BoolCircuit, GateCountReport) {
+        let circuit = rebuild(self, |circuit_builder, gate, new_inputs| {
+            circuit_builder.gate(gate.gate_type.clone(), new_inputs)
+        });
+        let report = GateCountReport::new(self, &circuit);
+        (circuit, report)
+    }
+
+    /// Propagates the constant gates through XOR/AND/OR/NOT gates, e.g., `AND(false, x) = false` and `XOR(true, x) = NOT(x)`, and then removes the dead gates.
+    /// Gates of the other types are evaluated only if all of their inputs are constants.
+    pub fn fold_constants(&self) -> (BoolCircuit, GateCountReport) {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let mut folded = HashMap::<GateId, Folded>::new();
+        for (idx, input) in circuit_builder
+            .input(self.num_input())
+            .into_iter()
+            .enumerate()
+        {
+            folded.insert(GateId::new(idx), Folded::Gate(input));
+        }
+        for gate in sort_gates(self) {
+            let gate_type_id = gate.gate_type.gate_type_id();
+            if gate_type_id == BoolGateTypeId::Input as u64
+                || gate_type_id == BoolGateTypeId::Output as u64
+            {
+                continue;
+            }
+            let inputs = gate
+                .inputs
+                .iter()
+                .map(|input_gate| folded[&input_gate.gate_id].clone())
+                .collect_vec();
+            let new_gate = fold_gate(&mut circuit_builder, &gate, &inputs);
+            folded.insert(gate.gate_id, new_gate);
+        }
+        let outputs = self
+            .output_gates
+            .iter()
+            .map(|gate| {
+                let source = if gate.gate_type.gate_type_id() == BoolGateTypeId::Output as u64 {
+                    &gate.inputs[0]
+                } else {
+                    gate
+                };
+                folded[&source.gate_id].to_gate(&mut circuit_builder)
+            })
+            .collect_vec();
+        // Folding leaves behind the gates whose outputs turned out to be unused.
+        let (circuit, _) = circuit_builder.output(outputs).remove_dead_gates();
+        let report = GateCountReport::new(self, &circuit);
+        (circuit, report)
+    }
+
+    /// Replaces every `NOT(NOT(x))` with `x`.
+    pub fn remove_double_nots(&self) -> (BoolCircuit, GateCountReport) {
+        let circuit = rebuild(self, |circuit_builder, gate, new_inputs| {
+            if gate.gate_type.gate_type_id() == BoolGateTypeId::Not as u64
+                && new_inputs[0].gate_type.gate_type_id() == BoolGateTypeId::Not as u64
+            {
+                new_inputs[0].inputs[0].clone()
+            } else {
+                circuit_builder.gate(gate.gate_type.clone(), new_inputs)
+            }
+        });
+        let (circuit, _) = circuit.remove_dead_gates();
+        let report = GateCountReport::new(self, &circuit);
+        (circuit, report)
+    }
+
+    /// Rewrites every `OR(a, b)` into XOR/AND gates.
+    /// If `a` and `b` are never true at the same time, e.g., in the carry `OR(AND(a, b), AND(XOR(a, b), c))` of a full adder, it becomes `XOR(a, b)`, which removes a multiplicative gate.
+    /// Otherwise it becomes `XOR(XOR(a, b), AND(a, b))`, whose gates can be shared with the existing ones by [`BoolCircuit::eliminate_common_subexpressions`].
+    pub fn rewrite_ors(&self) -> (BoolCircuit, GateCountReport) {
+        let circuit = rebuild(self, |circuit_builder, gate, new_inputs| {
+            if gate.gate_type.gate_type_id() != BoolGateTypeId::Or as u64 {
+                return circuit_builder.gate(gate.gate_type.clone(), new_inputs);
+            }
+            let xor = circuit_builder.xor(&new_inputs[0], &new_inputs[1]);
+            if are_exclusive(&new_inputs[0], &new_inputs[1]) {
+                return xor;
+            }
+            let and = circuit_builder.and(&new_inputs[0], &new_inputs[1]);
+            circuit_builder.xor(&xor, &and)
+        });
+        let report = GateCountReport::new(self, &circuit);
+        (circuit, report)
+    }
+
+    /// Merges the gates with the same gate type id and the same inputs by structural hashing, where the inputs of XOR/AND/OR gates are unordered.
+    /// Only the built-in gate types determined by their gate type ids are merged.
+    pub fn eliminate_common_subexpressions(&self) -> (BoolCircuit, GateCountReport) {
+        let mut hashed_gates = HashMap::<(u64, Vec<GateId>), Arc<BoolGate>>::new();
+        let circuit = rebuild(self, |circuit_builder, gate, new_inputs| {
+            let gate_type_id = gate.gate_type.gate_type_id();
+            let is_commutative = gate_type_id == BoolGateTypeId::Xor as u64
+                || gate_type_id == BoolGateTypeId::And as u64
+                || gate_type_id == BoolGateTypeId::Or as u64;
+            let is_hashable = is_commutative
+                || gate_type_id == BoolGateTypeId::Not as u64
+                || gate_type_id == BoolGateTypeId::False as u64
+                || gate_type_id == BoolGateTypeId::True as u64;
+            if !is_hashable {
+                return circuit_builder.gate(gate.gate_type.clone(), new_inputs);
+            }
+            let mut input_ids = new_inputs.iter().map(|gate| gate.gate_id).collect_vec();
+            if is_commutative {
+                input_ids.sort();
+            }
+            hashed_gates
+                .entry((gate_type_id, input_ids))
+                .or_insert_with(|| circuit_builder.gate(gate.gate_type.clone(), new_inputs))
+                .clone()
+        });
+        let (circuit, _) = circuit.remove_dead_gates();
+        let report = GateCountReport::new(self, &circuit);
+        (circuit, report)
+    }
+
+    /// Runs `passes` in order, checking after each pass that the outputs are unchanged on `num_samples` random inputs.
+    /// It returns the optimized circuit and the report of each pass, or [`crate::Error::NonEquivalentCircuit`] if some pass changes the outputs.
+    pub fn optimize<R: Rng>(
+        &self,
+        passes: &[OptimizationPass],
+        num_samples: usize,
+        rng: &mut R,
+    ) -> Result<(BoolCircuit, Vec<(OptimizationPass, GateCountReport)>), crate::Error> {
+        let inputs = (0..num_samples)
+            .map(|_| {
+                (0..self.num_input())
+                    .map(|_| rng.gen_bool(0.5))
+                    .collect_vec()
+            })
+            .collect_vec();
+        let expected_outputs = self.eval_batch(&inputs);
+        let mut circuit = self.clone();
+        let mut reports = vec![];
+        for pass in passes {
+            let (optimized, report) = match pass {
+                OptimizationPass::FoldConstants => circuit.fold_constants(),
+                OptimizationPass::RemoveDoubleNots => circuit.remove_double_nots(),
+                OptimizationPass::RewriteOrs => circuit.rewrite_ors(),
+                OptimizationPass::EliminateCommonSubexpressions => {
+                    circuit.eliminate_common_subexpressions()
+                }
+                OptimizationPass::RemoveDeadGates => circuit.remove_dead_gates(),
+            };
+            if optimized.eval_batch(&inputs) != expected_outputs {
+                return Err(crate::Error::NonEquivalentCircuit(format!("{:?}", pass)));
+            }
+            circuit = optimized;
+            reports.push((*pass, report));
+        }
+        Ok((circuit, reports))
+    }
+}
+
+/// An optimization pass of [`BoolCircuit::optimize`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OptimizationPass {
+    FoldConstants,
+    RemoveDoubleNots,
+    RewriteOrs,
+    EliminateCommonSubexpressions,
+    RemoveDeadGates,
+}
+
+impl OptimizationPass {
+    /// Returns all the passes in the recommended order.
+    pub fn all() -> Vec<Self> {
+        vec![
+            Self::FoldConstants,
+            Self::RemoveDoubleNots,
+            Self::RewriteOrs,
+            Self::EliminateCommonSubexpressions,
+            Self::RemoveDeadGates,
+        ]
+    }
+}
+
+/// The depth of the cones examined by [`are_exclusive`].
+const EXCLUSIVITY_CONE_DEPTH: usize = 4;
+/// The maximum number of the leaves of the cones examined by [`are_exclusive`].
+const EXCLUSIVITY_MAX_LEAVES: usize = 6;
+
+/// Returns true only if `a` and `b` are never true at the same time.
+/// It treats the gates at depth [`EXCLUSIVITY_CONE_DEPTH`] below `a` and `b` as free variables and tries all of their assignments, so it may miss some exclusive pairs but never reports a wrong one.
+fn are_exclusive(a: &Arc<BoolGate>, b: &Arc<BoolGate>) -> bool {
+    let mut leaves = vec![];
+    if !collect_leaves(a, EXCLUSIVITY_CONE_DEPTH, &mut leaves)
+        || !collect_leaves(b, EXCLUSIVITY_CONE_DEPTH, &mut leaves)
+    {
+        return false;
+    }
+    (0..1u64 << leaves.len()).all(|assignment| {
+        !(eval_cone(a, EXCLUSIVITY_CONE_DEPTH, &leaves, assignment)
+            && eval_cone(b, EXCLUSIVITY_CONE_DEPTH, &leaves, assignment))
+    })
+}
+
+fn is_logic_gate(gate: &BoolGate) -> bool {
+    let gate_type_id = gate.gate_type.gate_type_id();
+    [
+        BoolGateTypeId::Xor,
+        BoolGateTypeId::And,
+        BoolGateTypeId::Or,
+        BoolGateTypeId::Not,
+        BoolGateTypeId::False,
+        BoolGateTypeId::True,
+    ]
+    .into_iter()
+    .any(|id| id as u64 == gate_type_id)
+}
+
+/// Collects the leaves of the cone of `gate` of depth `depth`, returning false if there are too many leaves.
+fn collect_leaves(gate: &Arc<BoolGate>, depth: usize, leaves: &mut Vec<GateId>) -> bool {
+    if depth == 0 || !is_logic_gate(gate) {
+        if !leaves.contains(&gate.gate_id) {
+            leaves.push(gate.gate_id);
+        }
+        return leaves.len() <= EXCLUSIVITY_MAX_LEAVES;
+    }
+    gate.inputs
+        .iter()
+        .all(|input| collect_leaves(input, depth - 1, leaves))
+}
+
+/// Evaluates the cone of `gate`, where the i-th leaf takes the i-th bit of `assignment`.
+fn eval_cone(gate: &Arc<BoolGate>, depth: usize, leaves: &[GateId], assignment: u64) -> bool {
+    if depth == 0 || !is_logic_gate(gate) {
+        let idx = leaves.iter().position(|id| *id == gate.gate_id).unwrap();
+        return (assignment >> idx) & 1 == 1;
+    }
+    let input = gate
+        .inputs
+        .iter()
+        .map(|input| eval_cone(input, depth - 1, leaves, assignment))
+        .collect_vec();
+    gate.gate_type.eval(&input)[0]
+}
+
+/// Rebuilds `circuit` in a topological order, mapping every gate except for the input and output gates with `f`, which takes the gate and its new inputs.
+pub(crate) fn rebuild<F>(circuit: &BoolCircuit, f: F) -> BoolCircuit
+where
+    F: FnMut(&mut BoolCircuitBuilder, &BoolGate, Vec<Arc<BoolGate>>) -> Arc<BoolGate>,
+{
+    let mut circuit_builder = BoolCircuitBuilder::new();
+    let inputs = circuit_builder.input(circuit.num_input());
+    let outputs = rebuild_into(&mut circuit_builder, circuit, &inputs, f);
+    circuit_builder.output(outputs)
+}
+
+/// Adds the gates of `circuit` reachable from its outputs to `circuit_builder` with `inputs` as its inputs, mapping them with `f` like [`rebuild`], and returns the new gates that its outputs refer to.
+pub(crate) fn rebuild_into<F>(
+    circuit_builder: &mut BoolCircuitBuilder,
+    circuit: &BoolCircuit,
+    inputs: &[Arc<BoolGate>],
+    mut f: F,
+) -> Vec<Arc<BoolGate>>
+where
+    F: FnMut(&mut BoolCircuitBuilder, &BoolGate, Vec<Arc<BoolGate>>) -> Arc<BoolGate>,
+{
+    let mut new_gates = HashMap::<GateId, Arc<BoolGate>>::new();
+    for (idx, input) in inputs.iter().enumerate() {
+        new_gates.insert(GateId::new(idx), input.clone());
+    }
+    for gate in sort_gates(circuit) {
+        let gate_type_id = gate.gate_type.gate_type_id();
+        if gate_type_id == BoolGateTypeId::Input as u64
+            || gate_type_id == BoolGateTypeId::Output as u64
+        {
+            continue;
+        }
+        let new_inputs = gate
+            .inputs
+            .iter()
+            .map(|input_gate| new_gates[&input_gate.gate_id].clone())
+            .collect_vec();
+        let new_gate = f(circuit_builder, &gate, new_inputs);
+        new_gates.insert(gate.gate_id, new_gate);
+    }
+    circuit
+        .output_gates
+        .iter()
+        .map(|gate| output_source(gate, &new_gates))
+        .collect()
+}
+
+/// Returns the new gate that the output gate `gate` should output.
+fn output_source(
+    gate: &Arc<BoolGate>,
+    new_gates: &HashMap<GateId, Arc<BoolGate>>,
+) -> Arc<BoolGate> {
+    if gate.gate_type.gate_type_id() == BoolGateTypeId::Output as u64 {
+        new_gates[&gate.inputs[0].gate_id].clone()
+    } else {
+        new_gates[&gate.gate_id].clone()
+    }
+}
+
+fn fold_gate(
+    circuit_builder: &mut BoolCircuitBuilder,
+    gate: &BoolGate,
+    inputs: &[Folded],
+) -> Folded {
+    let gate_type_id = gate.gate_type.gate_type_id();
+    match (gate_type_id, inputs) {
+        (id, _) if id == BoolGateTypeId::False as u64 => Folded::Constant(false),
+        (id, _) if id == BoolGateTypeId::True as u64 => Folded::Constant(true),
+        (id, [a]) if id == BoolGateTypeId::Not as u64 => match a {
+            Folded::Constant(a) => Folded::Constant(!a),
+            Folded::Gate(a) => Folded::Gate(circuit_builder.not(a)),
+        },
+        (id, [a, b]) if id == BoolGateTypeId::Xor as u64 => match (a, b) {
+            (Folded::Constant(a), Folded::Constant(b)) => Folded::Constant(a ^ b),
+            (Folded::Constant(false), x) | (x, Folded::Constant(false)) => x.clone(),
+            (Folded::Constant(true), Folded::Gate(x))
+            | (Folded::Gate(x), Folded::Constant(true)) => Folded::Gate(circuit_builder.not(x)),
+            (Folded::Gate(a), Folded::Gate(b)) => Folded::Gate(circuit_builder.xor(a, b)),
+        },
+        (id, [a, b]) if id == BoolGateTypeId::And as u64 => match (a, b) {
+            (Folded::Constant(false), _) | (_, Folded::Constant(false)) => Folded::Constant(false),
+            (Folded::Constant(true), x) | (x, Folded::Constant(true)) => x.clone(),
+            (Folded::Gate(a), Folded::Gate(b)) => Folded::Gate(circuit_builder.and(a, b)),
+        },
+        (id, [a, b]) if id == BoolGateTypeId::Or as u64 => match (a, b) {
+            (Folded::Constant(true), _) | (_, Folded::Constant(true)) => Folded::Constant(true),
+            (Folded::Constant(false), x) | (x, Folded::Constant(false)) => x.clone(),
+            (Folded::Gate(a), Folded::Gate(b)) => Folded::Gate(circuit_builder.or(a, b)),
+        },
+        _ => {
+            let constants = inputs
+                .iter()
+                .map(|input| match input {
+                    Folded::Constant(bit) => Some(*bit),
+                    Folded::Gate(_) => None,
+                })
+                .collect::<Option<Vec<_>>>();
+            match constants {
+                Some(constants) if gate.gate_type.num_output() == 1 => {
+                    Folded::Constant(gate.gate_type.eval(&constants)[0])
+                }
+                _ => {
+                    let new_inputs = inputs
+                        .iter()
+                        .map(|input| input.to_gate(circuit_builder))
+                        .collect_vec();
+                    Folded::Gate(circuit_builder.gate(gate.gate_type.clone(), new_inputs))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_equivalent(circuit1: &BoolCircuit, circuit2: &BoolCircuit) {
+        assert_eq!(circuit1.num_input(), circuit2.num_input());
+        let mut evaluator1 = PlainBoolCircuitEvaluator::new(Arc::new(circuit1.clone()));
+        let mut evaluator2 = FlatBoolCircuitEvaluator::new(Arc::new(circuit2.clone()));
+        for bits in 0..(1 << circuit1.num_input()) {
+            let input = (0..circuit1.num_input())
+                .map(|idx| (bits >> idx) & 1 == 1)
+                .collect_vec();
+            assert_eq!(evaluator1.eval(&input), evaluator2.eval(&input));
+        }
+    }
+
+    #[test]
+    fn test_constant_gates() {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let input = circuit_builder.input(1);
+        let zero = circuit_builder.constant(false);
+        let one = circuit_builder.constant(true);
+        assert_eq!(circuit_builder.constant(true).gate_id, one.gate_id);
+        let xor = circuit_builder.xor(&input[0], &one);
+        let circuit = circuit_builder.output(vec![zero, one, xor]);
+        let mut evaluator = PlainBoolCircuitEvaluator::new(Arc::new(circuit.clone()));
+        assert_eq!(evaluator.eval(&[false]), vec![false, true, true]);
+        assert_eq!(evaluator.eval(&[true]), vec![false, true, false]);
+        assert_eq!(
+            circuit.eval_batch(&[vec![false], vec![true]]),
+            vec![vec![false, true, true], vec![false, true, false]]
+        );
+        let decoded = BoolCircuit::from_bytes(&circuit.to_bytes()).unwrap();
+        assert_equivalent(&circuit, &decoded);
+        let decoded = BoolCircuit::from_bristol(&circuit.to_bristol().unwrap()).unwrap();
+        assert_equivalent(&circuit, &decoded);
+    }
+
+    #[test]
+    fn test_fold_constants() {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let input = circuit_builder.input(2);
+        let zero = circuit_builder.constant(false);
+        let one = circuit_builder.constant(true);
+        let and = circuit_builder.and(&input[0], &zero);
+        let or = circuit_builder.or(&and, &input[1]);
+        let xor = circuit_builder.xor(&or, &one);
+        let not = circuit_builder.not(&zero);
+        let and2 = circuit_builder.and(&not, &xor);
+        let or2 = circuit_builder.or(&and2, &one);
+        let circuit = circuit_builder.output(vec![and2, or2, and]);
+        let (folded, report) = circuit.fold_constants();
+        assert_equivalent(&circuit, &folded);
+        // Only `NOT(x1)` and the constants `true` and `false` are left.
+        assert_eq!(report.num_gates_before, 8);
+        assert_eq!(report.num_gates_after, 3);
+        assert_eq!(report.num_removed_gates(), 5);
+        assert_eq!(report.to_string(), "8 gates -> 3 gates (5 removed)");
+        assert_eq!(folded.num_gates(), 2 + 3 + 3);
+    }
+
+    #[test]
+    fn test_fold_constants_through_chips() {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let chip = {
+            let mut chip_builder = BoolCircuitBuilder::new();
+            let input = chip_builder.input(2);
+            let and = chip_builder.and(&input[0], &input[1]);
+            chip_builder.output(vec![and])
+        };
+        let chip = circuit_builder.register_chip(chip);
+        let input = circuit_builder.input(1);
+        let one = circuit_builder.constant(true);
+        let constant_call = circuit_builder.call_chip(&[one.clone(), one.clone()], &chip);
+        let call = circuit_builder.call_chip(&[input[0].clone(), one], &chip);
+        let circuit = circuit_builder.output([constant_call, call].concat());
+        let (folded, report) = circuit.fold_constants();
+        assert_equivalent(&circuit, &folded);
+        // The first call is evaluated and the second one is kept.
+        assert_eq!(report.num_gates_after, 2);
+    }
+
+    #[test]
+    fn test_remove_dead_gates() {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let input = circuit_builder.input(3);
+        let xor = circuit_builder.xor(&input[0], &input[1]);
+        for _ in 0..10 {
+            circuit_builder.not(&xor);
+        }
+        let and = circuit_builder.and(&xor, &input[1]);
+        let circuit = circuit_builder.output(vec![and, input[2].clone()]);
+        let (optimized, report) = circuit.remove_dead_gates();
+        assert_equivalent(&circuit, &optimized);
+        assert_eq!(report.num_gates_before, 2);
+        assert_eq!(report.num_gates_after, 2);
+        assert_eq!(circuit.num_gates(), 3 + 12 + 2);
+        assert_eq!(optimized.num_gates(), 3 + 2 + 2);
+    }
+
+    fn count_gates_of_type(circuit: &BoolCircuit, gate_type_id: BoolGateTypeId) -> usize {
+        sort_gates(circuit)
+            .iter()
+            .filter(|gate| gate.gate_type.gate_type_id() == gate_type_id.clone() as u64)
+            .count()
+    }
+
+    #[test]
+    fn test_remove_double_nots() {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let input = circuit_builder.input(2);
+        let not1 = circuit_builder.not(&input[0]);
+        let not2 = circuit_builder.not(&not1);
+        let not3 = circuit_builder.not(&not2);
+        let and = circuit_builder.and(&not2, &input[1]);
+        let circuit = circuit_builder.output(vec![and, not3]);
+        let (optimized, report) = circuit.remove_double_nots();
+        assert_equivalent(&circuit, &optimized);
+        // `not2` becomes `x0` and `not3` becomes `not1`.
+        assert_eq!(report.to_string(), "4 gates -> 2 gates (2 removed)");
+    }
+
+    #[test]
+    fn test_eliminate_common_subexpressions() {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let input = circuit_builder.input(3);
+        let xor1 = circuit_builder.xor(&input[0], &input[1]);
+        let xor2 = circuit_builder.xor(&input[1], &input[0]);
+        let and1 = circuit_builder.and(&xor1, &input[2]);
+        let and2 = circuit_builder.and(&input[2], &xor2);
+        let not1 = circuit_builder.not(&and1);
+        let not2 = circuit_builder.not(&and2);
+        let or = circuit_builder.or(&not1, &not2);
+        let circuit = circuit_builder.output(vec![or, not2]);
+        let (optimized, report) = circuit.eliminate_common_subexpressions();
+        assert_equivalent(&circuit, &optimized);
+        assert_eq!(report.num_gates_before, 7);
+        assert_eq!(report.num_gates_after, 4);
+    }
+
+    #[test]
+    fn test_rewrite_ors() {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let input = circuit_builder.input(4);
+        // The carry of a full adder, whose ORed terms are exclusive.
+        let xor = circuit_builder.xor(&input[0], &input[1]);
+        let and1 = circuit_builder.and(&input[0], &input[1]);
+        let and2 = circuit_builder.and(&xor, &input[2]);
+        let carry = circuit_builder.or(&and1, &and2);
+        let or = circuit_builder.or(&input[0], &input[3]);
+        let circuit = circuit_builder.output(vec![carry, or]);
+        let (optimized, _) = circuit.rewrite_ors();
+        assert_equivalent(&circuit, &optimized);
+        assert_eq!(count_gates_of_type(&optimized, BoolGateTypeId::Or), 0);
+        // The carry needs no additional AND gate, while `OR(x0, x3)` needs one.
+        assert_eq!(count_gates_of_type(&optimized, BoolGateTypeId::And), 3);
+    }
+
+    #[test]
+    fn test_optimize() {
+        let mut rng = rand::thread_rng();
+        let width = 8;
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let mut layer = circuit_builder.input(width);
+        layer.push(circuit_builder.constant(false));
+        layer.push(circuit_builder.constant(true));
+        for _ in 0..8 {
+            let mut next_layer = (0..width)
+                .map(|_| {
+                    let a = &layer[rng.gen_range(0..layer.len())];
+                    let b = &layer[rng.gen_range(0..layer.len())];
+                    match rng.gen_range(0..5) {
+                        0 => circuit_builder.xor(a, b),
+                        1 => circuit_builder.and(a, b),
+                        2 => circuit_builder.or(a, b),
+                        3 => circuit_builder.not(a),
+                        _ => {
+                            let not = circuit_builder.not(a);
+                            circuit_builder.not(&not)
+                        }
+                    }
+                })
+                .collect_vec();
+            // Duplicate some gates so that CSE has something to merge.
+            next_layer.push(circuit_builder.xor(&layer[1], &layer[0]));
+            next_layer.push(circuit_builder.xor(&layer[0], &layer[1]));
+            layer = next_layer;
+        }
+        let circuit = circuit_builder.output(layer);
+        let (optimized, reports) = circuit
+            .optimize(&OptimizationPass::all(), 256, &mut rng)
+            .unwrap();
+        assert_equivalent(&circuit, &optimized);
+        assert_eq!(reports.len(), OptimizationPass::all().len());
+        assert_eq!(reports[0].1.num_gates_before, count_gates(&circuit));
+        assert_eq!(
+            reports.last().unwrap().1.num_gates_after,
+            count_gates(&optimized)
+        );
+        assert_eq!(count_gates_of_type(&optimized, BoolGateTypeId::Or), 0);
+    }
+}
@@ -0,0 +1,356 @@
+use super::*;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// The shape of the circuits generated by [`BoolCircuit::random`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RandomCircuitConfig {
+    pub num_input: usize,
+    pub num_output: usize,
+    /// The number of the gates except for the input and output gates, some of which may be unreachable from the outputs.
+    pub num_gates: usize,
+    /// The depth of the circuit as in [`crate::circuit::stats::CircuitStats::depth`].
+    pub depth: usize,
+    /// The relative frequencies of the XOR, AND, OR and NOT gates.
+    pub xor_weight: u32,
+    pub and_weight: u32,
+    pub or_weight: u32,
+    pub not_weight: u32,
+}
+
+impl RandomCircuitConfig {
+    /// Returns the config where all gate types are equally likely.
+    pub fn new(num_input: usize, num_output: usize, num_gates: usize, depth: usize) -> Self {
+        Self {
+            num_input,
+            num_output,
+            num_gates,
+            depth,
+            xor_weight: 1,
+            and_weight: 1,
+            or_weight: 1,
+            not_weight: 1,
+        }
+    }
+
+    fn check(&self) -> Result<(), crate::Error> {
+        let invalid = |reason: &str| Err(crate::Error::InvalidCircuitConfig(reason.to_string()));
+        if self.num_input == 0 || self.num_output == 0 {
+            return invalid("the numbers of the inputs and outputs must be positive");
+        }
+        if self.depth > self.num_gates || (self.depth == 0 && self.num_gates > 0) {
+            return invalid("every layer must have at least one gate");
+        }
+        Ok(())
+    }
+}
+
+impl BoolCircuit {
+    /// Generates a random circuit of `config.depth` layers of gates.
+    /// Every gate in a layer takes its first input from the previous layer and the other one from any earlier layer including the inputs, and at least one output is taken from the last layer.
+    pub fn random<R: Rng>(config: &RandomCircuitConfig, rng: &mut R) -> Result<Self, crate::Error> {
+        config.check()?;
+        let gate_types = WeightedIndex::new([
+            config.xor_weight,
+            config.and_weight,
+            config.or_weight,
+            config.not_weight,
+        ])
+        .map_err(|err| crate::Error::InvalidCircuitConfig(err.to_string()))?;
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let mut layers = vec![circuit_builder.input(config.num_input)];
+        // The gates of all layers so far.
+        let mut pool = layers[0].clone();
+        for layer_idx in 0..config.depth {
+            let num_layer_gates = config.num_gates / config.depth
+                + (layer_idx < config.num_gates % config.depth) as usize;
+            let prev_layer = &layers[layer_idx];
+            let layer = (0..num_layer_gates)
+                .map(|_| {
+                    let a = &prev_layer[rng.gen_range(0..prev_layer.len())];
+                    let b = &pool[rng.gen_range(0..pool.len())];
+                    match gate_types.sample(rng) {
+                        0 => circuit_builder.xor(a, b),
+                        1 => circuit_builder.and(a, b),
+                        2 => circuit_builder.or(a, b),
+                        _ => circuit_builder.not(a),
+                    }
+                })
+                .collect_vec();
+            pool.extend(layer.iter().cloned());
+            layers.push(layer);
+        }
+        let last_layer = layers.last().unwrap();
+        let candidates = if config.num_gates == 0 {
+            &pool[..]
+        } else {
+            &pool[config.num_input..]
+        };
+        let deepest_idx = rng.gen_range(0..config.num_output);
+        let outputs = (0..config.num_output)
+            .map(|idx| {
+                if idx == deepest_idx {
+                    last_layer[rng.gen_range(0..last_layer.len())].clone()
+                } else {
+                    candidates[rng.gen_range(0..candidates.len())].clone()
+                }
+            })
+            .collect_vec();
+        Ok(circuit_builder.output(outputs))
+    }
+
+    /// Generates a random circuit like [`BoolCircuit::random`], which is always the same for the same `config` and `seed`.
+    pub fn random_from_seed(config: &RandomCircuitConfig, seed: u64) -> Result<Self, crate::Error> {
+        Self::random(config, &mut StdRng::seed_from_u64(seed))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::circuit::optimize::OptimizationPass;
+    use crate::phfe::{Phfe, PhfeFunc};
+    use crate::poly::{Variable, VariableType};
+    use ark_bn254::{Bn254, Fr};
+    use ark_ec::{pairing::PairingOutput, Group};
+    use ark_std::{end_timer, start_timer};
+    use nalgebra::DVector;
+
+    const NUM_SEEDS: u64 = 8;
+
+    fn configs() -> Vec<RandomCircuitConfig> {
+        vec![
+            RandomCircuitConfig::new(4, 3, 16, 4),
+            RandomCircuitConfig::new(8, 8, 64, 8),
+            RandomCircuitConfig {
+                xor_weight: 4,
+                and_weight: 1,
+                or_weight: 0,
+                not_weight: 1,
+                ..RandomCircuitConfig::new(6, 2, 40, 20)
+            },
+            RandomCircuitConfig::new(3, 2, 0, 0),
+        ]
+    }
+
+    fn random_inputs(num_input: usize, num_samples: usize, rng: &mut StdRng) -> Vec<Vec<bool>> {
+        (0..num_samples)
+            .map(|_| (0..num_input).map(|_| rng.gen_bool(0.5)).collect())
+            .collect()
+    }
+
+    /// Asserts that `circuit` has the same outputs as `expected` under [`PlainBoolCircuitEvaluator`].
+    fn assert_equivalent(expected: &BoolCircuit, circuit: &BoolCircuit, rng: &mut StdRng) {
+        let mut expected_evaluator = PlainBoolCircuitEvaluator::new(Arc::new(expected.clone()));
+        let mut evaluator = PlainBoolCircuitEvaluator::new(Arc::new(circuit.clone()));
+        for input in random_inputs(expected.num_input(), 64, rng) {
+            assert_eq!(
+                evaluator.try_eval(&input).unwrap(),
+                expected_evaluator.eval(&input)
+            );
+        }
+    }
+
+    #[test]
+    fn test_random_circuit_shape() {
+        for config in configs() {
+            for seed in 0..NUM_SEEDS {
+                let circuit = BoolCircuit::random_from_seed(&config, seed).unwrap();
+                assert!(circuit.validate().is_ok());
+                let stats = circuit.stats();
+                assert_eq!(stats.num_input, config.num_input);
+                assert_eq!(stats.num_output, config.num_output);
+                assert_eq!(stats.depth, config.depth);
+                assert!(stats.num_gates <= config.num_gates);
+                assert_eq!(
+                    BoolCircuit::random_from_seed(&config, seed)
+                        .unwrap()
+                        .digest(),
+                    circuit.digest()
+                );
+            }
+        }
+        let config = configs()[2].clone();
+        let stats = BoolCircuit::random_from_seed(&config, 0).unwrap().stats();
+        assert!(!stats.gate_counts.contains_key(&(BoolGateTypeId::Or as u64)));
+        assert!(matches!(
+            BoolCircuit::random_from_seed(&RandomCircuitConfig::new(2, 1, 3, 4), 0),
+            Err(crate::Error::InvalidCircuitConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_random_evaluators() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for config in configs() {
+            for seed in 0..NUM_SEEDS {
+                let circuit = Arc::new(BoolCircuit::random_from_seed(&config, seed).unwrap());
+                let mut plain_evaluator = PlainBoolCircuitEvaluator::new(circuit.clone());
+                let mut flat_evaluator =
+                    FlatBoolCircuitEvaluator::try_new(circuit.clone()).unwrap();
+                let inputs = random_inputs(config.num_input, 100, &mut rng);
+                let batch_outputs = circuit.eval_batch(&inputs);
+                for (input, batch_output) in inputs.iter().zip(batch_outputs) {
+                    let expected = plain_evaluator.eval(input);
+                    assert_eq!(flat_evaluator.try_eval(input).unwrap(), expected);
+                    assert_eq!(batch_output, expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_random_transformations() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for config in configs() {
+            for seed in 0..NUM_SEEDS {
+                let circuit = BoolCircuit::random_from_seed(&config, seed).unwrap();
+                let (optimized, _) = circuit
+                    .optimize(&OptimizationPass::all(), 64, &mut rng)
+                    .unwrap();
+                assert_equivalent(&circuit, &optimized, &mut rng);
+                let (rebalanced, report) = circuit.rebalance();
+                assert!(report.depth_after <= report.depth_before);
+                assert_equivalent(&circuit, &rebalanced, &mut rng);
+                let bristol = BoolCircuit::from_bristol(&circuit.to_bristol().unwrap()).unwrap();
+                assert_equivalent(&circuit, &bristol, &mut rng);
+                let decoded = BoolCircuit::from_bytes(&circuit.to_bytes()).unwrap();
+                assert_eq!(decoded.digest(), circuit.digest());
+            }
+        }
+    }
+
+    #[test]
+    fn test_random_arithmetization() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for config in configs() {
+            for seed in 0..NUM_SEEDS {
+                let circuit = Arc::new(BoolCircuit::random_from_seed(&config, seed).unwrap());
+                let polys = circuit.to_polynomials::<Fr>(None).unwrap();
+                let mut evaluator = PlainBoolCircuitEvaluator::new(circuit.clone());
+                for input in random_inputs(config.num_input, 16, &mut rng) {
+                    let assignment = public_assignment(&input);
+                    for (poly, bit) in polys.iter().zip(evaluator.eval(&input)) {
+                        assert_eq!(poly.eval(&assignment), Fr::from(bit as u64));
+                    }
+                }
+            }
+        }
+    }
+
+    fn public_assignment(input: &[bool]) -> HashMap<Variable, Fr> {
+        input
+            .iter()
+            .enumerate()
+            .map(|(idx, bit)| {
+                (
+                    Variable {
+                        index: idx as u32,
+                        variable_type: VariableType::Public,
+                    },
+                    Fr::from(*bit as u64),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    #[ignore]
+    fn bench_random_circuit_evaluation() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for num_gates in [1 << 10, 1 << 14, 1 << 18] {
+            let config = RandomCircuitConfig::new(64, 64, num_gates, 32);
+            let circuit = Arc::new(BoolCircuit::random_from_seed(&config, 0).unwrap());
+            let inputs = random_inputs(config.num_input, 256, &mut rng);
+            let plain_timer = start_timer!(|| format!("plain evaluator, {} gates", num_gates));
+            let mut plain_evaluator = PlainBoolCircuitEvaluator::new(circuit.clone());
+            let plain_outputs = inputs
+                .iter()
+                .map(|input| plain_evaluator.eval(input))
+                .collect_vec();
+            end_timer!(plain_timer);
+            let flat_timer = start_timer!(|| format!("flat evaluator, {} gates", num_gates));
+            let mut flat_evaluator = FlatBoolCircuitEvaluator::new(circuit.clone());
+            let flat_outputs = inputs
+                .iter()
+                .map(|input| flat_evaluator.eval(input))
+                .collect_vec();
+            end_timer!(flat_timer);
+            let batch_timer = start_timer!(|| format!("batch evaluator, {} gates", num_gates));
+            let batch_outputs = circuit.eval_batch(&inputs);
+            end_timer!(batch_timer);
+            assert_eq!(plain_outputs, flat_outputs);
+            assert_eq!(plain_outputs, batch_outputs);
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn bench_random_phfe_pipeline() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let (num_private_vars1, num_private_vars2) = (2, 3);
+        let k = 2;
+        for num_gates in [16, 64, 256] {
+            let config = RandomCircuitConfig::new(
+                8,
+                num_private_vars1 * num_private_vars2,
+                num_gates,
+                num_gates / 8,
+            );
+            let circuit = BoolCircuit::random_from_seed(&config, 0).unwrap();
+            let arithmetize_timer =
+                start_timer!(|| format!("arithmetization, {} gates", num_gates));
+            let polys = circuit.to_polynomials::<Fr>(None).unwrap();
+            end_timer!(arithmetize_timer);
+            let func = PhfeFunc::from_polys(
+                config.num_input,
+                num_private_vars1,
+                num_private_vars2,
+                polys,
+            )
+            .unwrap();
+            // The key generation requires more columns of `l0` than `k`, which a circuit of nearly linear outputs may not have, so such a circuit fails the benchmark rather than being skipped.
+            assert!(
+                func.l0.ncols() > k,
+                "l0 of the circuit of {} gates has {} columns, but the key generation requires more than k = {}",
+                num_gates,
+                func.l0.ncols(),
+                k
+            );
+            let phfe = Phfe::<Bn254>::new(
+                func.num_public_vars,
+                func.num_private_vars1,
+                func.num_private_vars2,
+                k,
+            );
+            let setup_timer = start_timer!(|| "setup keys");
+            let (mpk, msk) = phfe.setup(&mut rng);
+            end_timer!(setup_timer);
+            let input = random_inputs(config.num_input, 1, &mut rng).remove(0);
+            let x =
+                DVector::from_iterator(input.len(), input.iter().map(|bit| Fr::from(*bit as u64)));
+            let z1 = DVector::from_fn(num_private_vars1, |_, _| Fr::from(rng.gen_range(0..=1)));
+            let z2 = DVector::from_fn(num_private_vars2, |_, _| Fr::from(rng.gen_range(0..=1)));
+            let enc_timer = start_timer!(|| "encryption");
+            let ct = phfe.enc(&mpk, &x, &z1, &z2, &mut rng);
+            end_timer!(enc_timer);
+            let fsk_timer = start_timer!(|| "fsk generation");
+            let fsk = phfe.gen_fsk(&msk, &func, &mut rng);
+            end_timer!(fsk_timer);
+            let dec_timer = start_timer!(|| "decryption");
+            let out_gt = phfe.dec(&ct, &func, &fsk).unwrap();
+            end_timer!(dec_timer);
+            let mut evaluator = PlainBoolCircuitEvaluator::new(Arc::new(circuit));
+            let fx = DVector::from_iterator(
+                config.num_output,
+                evaluator
+                    .eval(&input)
+                    .into_iter()
+                    .map(|bit| Fr::from(bit as u64)),
+            );
+            let expected_out = (z1.kronecker(&z2).transpose() * &fx)[(0, 0)];
+            assert_eq!(out_gt, PairingOutput::generator() * expected_out);
+        }
+    }
+}
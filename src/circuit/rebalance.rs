@@ -0,0 +1,341 @@
+use super::*;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt;
+
+/// The depths of a circuit before and after [`BoolCircuit::rebalance`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DepthReport {
+    pub depth_before: usize,
+    pub depth_after: usize,
+    pub multiplicative_depth_before: usize,
+    pub multiplicative_depth_after: usize,
+}
+
+impl DepthReport {
+    pub fn new(before: &BoolCircuit, after: &BoolCircuit) -> Self {
+        let (before, after) = (before.stats(), after.stats());
+        Self {
+            depth_before: before.depth,
+            depth_after: after.depth,
+            multiplicative_depth_before: before.multiplicative_depth,
+            multiplicative_depth_after: after.multiplicative_depth,
+        }
+    }
+}
+
+impl fmt::Display for DepthReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "depth {} -> {}, multiplicative depth {} -> {}",
+            self.depth_before,
+            self.depth_after,
+            self.multiplicative_depth_before,
+            self.multiplicative_depth_after
+        )
+    }
+}
+
+/// The multiplicative depth and the depth of a gate, compared in this order.
+type Cost = (usize, usize);
+
+fn is_associative(gate_type_id: u64) -> bool {
+    gate_type_id == BoolGateTypeId::Xor as u64
+        || gate_type_id == BoolGateTypeId::And as u64
+        || gate_type_id == BoolGateTypeId::Or as u64
+}
+
+impl BoolCircuit {
+    /// Rewrites every maximal tree of XOR, AND or OR gates of the same type, whose inner gates have no other consumers, into a balanced tree.
+    /// The leaves are combined from the shallowest ones like Huffman coding, so that leaves arriving late are placed near the root.
+    /// In XOR trees, NOT gates are also absorbed by `XOR(NOT(a), b) = NOT(XOR(a, b))` and at most one NOT is applied to the shallowest leaf.
+    pub fn rebalance(&self) -> (BoolCircuit, DepthReport) {
+        let gates = sort_gates(self);
+        // The only consumer of each gate that has exactly one consumer.
+        let mut consumers = HashMap::<GateId, Option<Arc<BoolGate>>>::new();
+        for gate in gates.iter() {
+            for input_gate in gate.inputs.iter() {
+                consumers
+                    .entry(input_gate.gate_id)
+                    .and_modify(|consumer| *consumer = None)
+                    .or_insert_with(|| Some(gate.clone()));
+            }
+        }
+        // A gate is absorbed if it becomes a part of the tree of its only consumer.
+        let mut absorbed = HashSet::<GateId>::new();
+        for gate in gates.iter().rev() {
+            let Some(Some(consumer)) = consumers.get(&gate.gate_id) else {
+                continue;
+            };
+            let gate_type_id = gate.gate_type.gate_type_id();
+            let consumer_type_id = consumer.gate_type.gate_type_id();
+            let is_not = gate_type_id == BoolGateTypeId::Not as u64;
+            let is_xor = gate_type_id == BoolGateTypeId::Xor as u64;
+            if (is_associative(gate_type_id) && gate_type_id == consumer_type_id)
+                || (is_not && consumer_type_id == BoolGateTypeId::Xor as u64)
+                || (is_xor
+                    && consumer_type_id == BoolGateTypeId::Not as u64
+                    && absorbed.contains(&consumer.gate_id))
+            {
+                absorbed.insert(gate.gate_id);
+            }
+        }
+
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let mut new_gates = HashMap::<GateId, Arc<BoolGate>>::new();
+        let mut costs = HashMap::<GateId, Cost>::new();
+        for (idx, input) in circuit_builder
+            .input(self.num_input())
+            .into_iter()
+            .enumerate()
+        {
+            costs.insert(input.gate_id, (0, 0));
+            new_gates.insert(GateId::new(idx), input);
+        }
+        for gate in gates.iter() {
+            let gate_type_id = gate.gate_type.gate_type_id();
+            if gate_type_id == BoolGateTypeId::Input as u64
+                || gate_type_id == BoolGateTypeId::Output as u64
+                || absorbed.contains(&gate.gate_id)
+            {
+                continue;
+            }
+            let new_gate = if is_associative(gate_type_id) {
+                let (leaves, negate) = collect_leaves(gate, &absorbed);
+                let mut leaves = leaves
+                    .iter()
+                    .map(|leaf| new_gates[&leaf.gate_id].clone())
+                    .collect_vec();
+                if negate {
+                    let (idx, _) = leaves
+                        .iter()
+                        .enumerate()
+                        .min_by_key(|(_, leaf)| costs[&leaf.gate_id])
+                        .unwrap();
+                    leaves[idx] = build_gate(
+                        &mut circuit_builder,
+                        &mut costs,
+                        Box::new(NotGateType::new()),
+                        vec![leaves[idx].clone()],
+                    );
+                }
+                build_balanced_tree(&mut circuit_builder, &mut costs, gate, leaves)
+            } else {
+                let new_inputs = gate
+                    .inputs
+                    .iter()
+                    .map(|input_gate| new_gates[&input_gate.gate_id].clone())
+                    .collect_vec();
+                build_gate(
+                    &mut circuit_builder,
+                    &mut costs,
+                    gate.gate_type.clone(),
+                    new_inputs,
+                )
+            };
+            new_gates.insert(gate.gate_id, new_gate);
+        }
+        let outputs = self
+            .output_gates
+            .iter()
+            .map(|gate| {
+                if gate.gate_type.gate_type_id() == BoolGateTypeId::Output as u64 {
+                    new_gates[&gate.inputs[0].gate_id].clone()
+                } else {
+                    new_gates[&gate.gate_id].clone()
+                }
+            })
+            .collect_vec();
+        let circuit = circuit_builder.output(outputs);
+        let report = DepthReport::new(self, &circuit);
+        (circuit, report)
+    }
+}
+
+/// Returns the leaves of the tree rooted at `root` and whether an odd number of NOT gates are absorbed into it.
+fn collect_leaves(root: &Arc<BoolGate>, absorbed: &HashSet<GateId>) -> (Vec<Arc<BoolGate>>, bool) {
+    let mut leaves = vec![];
+    let mut negate = false;
+    let mut stack = root.inputs.iter().rev().cloned().collect_vec();
+    while let Some(gate) = stack.pop() {
+        if !absorbed.contains(&gate.gate_id) {
+            leaves.push(gate);
+            continue;
+        }
+        if gate.gate_type.gate_type_id() == BoolGateTypeId::Not as u64 {
+            negate = !negate;
+        }
+        stack.extend(gate.inputs.iter().rev().cloned());
+    }
+    (leaves, negate)
+}
+
+/// Combines `leaves` with gates of the type of `root`, always combining the two cheapest ones first.
+fn build_balanced_tree(
+    circuit_builder: &mut BoolCircuitBuilder,
+    costs: &mut HashMap<GateId, Cost>,
+    root: &BoolGate,
+    leaves: Vec<Arc<BoolGate>>,
+) -> Arc<BoolGate> {
+    // The index breaks ties so that the leaves keep their order.
+    let mut heap = leaves
+        .into_iter()
+        .enumerate()
+        .map(|(idx, leaf)| Reverse((costs[&leaf.gate_id], idx, ByGateId(leaf))))
+        .collect::<BinaryHeap<_>>();
+    let mut next_idx = heap.len();
+    loop {
+        let Reverse((_, _, ByGateId(a))) = heap.pop().unwrap();
+        let Some(Reverse((_, _, ByGateId(b)))) = heap.pop() else {
+            return a;
+        };
+        let gate = build_gate(circuit_builder, costs, root.gate_type.clone(), vec![a, b]);
+        heap.push(Reverse((costs[&gate.gate_id], next_idx, ByGateId(gate))));
+        next_idx += 1;
+    }
+}
+
+fn build_gate(
+    circuit_builder: &mut BoolCircuitBuilder,
+    costs: &mut HashMap<GateId, Cost>,
+    gate_type: Box<dyn BoolGateType>,
+    inputs: Vec<Arc<BoolGate>>,
+) -> Arc<BoolGate> {
+    let gate_type_id = gate_type.gate_type_id();
+    let (multiplicative_depth, depth) = inputs
+        .iter()
+        .map(|input| costs[&input.gate_id])
+        .fold((0, 0), |acc, cost| (acc.0.max(cost.0), acc.1.max(cost.1)));
+    let is_linear = gate_type_id == BoolGateTypeId::Xor as u64
+        || gate_type_id == BoolGateTypeId::Not as u64
+        || gate_type_id == BoolGateTypeId::False as u64
+        || gate_type_id == BoolGateTypeId::True as u64;
+    let cost = if inputs.is_empty() {
+        (0, 0)
+    } else if gate_type_id == BoolGateTypeId::Projection as u64 {
+        costs[&inputs[0].gate_id]
+    } else if is_linear {
+        (multiplicative_depth, depth + 1)
+    } else {
+        (multiplicative_depth + 1, depth + 1)
+    };
+    let gate = circuit_builder.gate(gate_type, inputs);
+    costs.insert(gate.gate_id, cost);
+    gate
+}
+
+/// A gate ordered by its id, used to put gates into [`BinaryHeap`].
+struct ByGateId(Arc<BoolGate>);
+
+impl PartialEq for ByGateId {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.gate_id == other.0.gate_id
+    }
+}
+
+impl Eq for ByGateId {}
+
+impl PartialOrd for ByGateId {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ByGateId {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.gate_id.cmp(&other.0.gate_id)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::Rng;
+
+    fn assert_equivalent(circuit1: &BoolCircuit, circuit2: &BoolCircuit) {
+        let mut rng = rand::thread_rng();
+        let mut evaluator1 = PlainBoolCircuitEvaluator::new(Arc::new(circuit1.clone()));
+        let mut evaluator2 = PlainBoolCircuitEvaluator::new(Arc::new(circuit2.clone()));
+        for _ in 0..256 {
+            let input = (0..circuit1.num_input())
+                .map(|_| rng.gen_bool(0.5))
+                .collect_vec();
+            assert_eq!(evaluator1.eval(&input), evaluator2.eval(&input));
+        }
+    }
+
+    #[test]
+    fn test_rebalance_chains() {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let input = circuit_builder.input(64);
+        let mut and = input[0].clone();
+        let mut xor = input[0].clone();
+        for (idx, gate) in input.iter().enumerate().skip(1) {
+            and = circuit_builder.and(&and, gate);
+            xor = circuit_builder.xor(&xor, gate);
+            if idx % 3 == 0 {
+                xor = circuit_builder.not(&xor);
+            }
+        }
+        let circuit = circuit_builder.output(vec![and, xor]);
+        let (rebalanced, report) = circuit.rebalance();
+        assert_equivalent(&circuit, &rebalanced);
+        assert_eq!(report.multiplicative_depth_before, 63);
+        assert_eq!(report.multiplicative_depth_after, 6);
+        assert_eq!(report.depth_before, 63 + 21);
+        // 21 NOTs cancel out into a single NOT.
+        assert_eq!(report.depth_after, 7);
+        assert_eq!(
+            report.to_string(),
+            "depth 84 -> 7, multiplicative depth 63 -> 6"
+        );
+    }
+
+    #[test]
+    fn test_rebalance_keeps_shared_gates() {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let input = circuit_builder.input(8);
+        let mut and = input[0].clone();
+        for gate in input.iter().skip(1) {
+            and = circuit_builder.and(&and, gate);
+        }
+        // The AND of the first four inputs is also used by another gate and an output.
+        let shared = circuit_builder.and(&input[0], &input[1]);
+        let shared = circuit_builder.and(&shared, &input[2]);
+        let shared = circuit_builder.and(&shared, &input[3]);
+        let and2 = circuit_builder.and(&shared, &input[4]);
+        let or = circuit_builder.or(&shared, &and);
+        let circuit = circuit_builder.output(vec![and, and2, or, shared]);
+        let (rebalanced, report) = circuit.rebalance();
+        assert_equivalent(&circuit, &rebalanced);
+        assert!(report.multiplicative_depth_after <= report.multiplicative_depth_before);
+        assert_eq!(report.multiplicative_depth_after, 4);
+    }
+
+    #[test]
+    fn test_rebalance_random_circuits() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..10 {
+            let width = 8;
+            let mut circuit_builder = BoolCircuitBuilder::new();
+            let mut gates = circuit_builder.input(width);
+            for _ in 0..200 {
+                let a = &gates[rng.gen_range(gates.len().saturating_sub(16)..gates.len())];
+                let b = &gates[rng.gen_range(0..gates.len())];
+                let gate = match rng.gen_range(0..5) {
+                    0 | 1 => circuit_builder.xor(a, b),
+                    2 => circuit_builder.and(a, b),
+                    3 => circuit_builder.or(a, b),
+                    _ => circuit_builder.not(a),
+                };
+                gates.push(gate);
+            }
+            let outputs = gates[gates.len() - width..].to_vec();
+            let circuit = circuit_builder.output(outputs);
+            let (rebalanced, report) = circuit.rebalance();
+            assert_equivalent(&circuit, &rebalanced);
+            assert!(report.multiplicative_depth_after <= report.multiplicative_depth_before);
+        }
+    }
+}
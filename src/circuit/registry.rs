@@ -0,0 +1,283 @@
+use super::*;
+
+/// A constructor of a gate type from the parameters returned by [`BoolGateType::params`].
+pub type GateTypeConstructor =
+    Arc<dyn Fn(&[u8]) -> Result<Box<dyn BoolGateType>, crate::Error> + Send + Sync>;
+
+/// A mapping from gate type ids to the constructors of the gate types, used to decode [`EncodedBoolCircuit`].
+/// [`BoolGateTypeRegistry::new`] registers all the built-in gate types except for [`ChipRefType`], which cannot be constructed without its chip.
+#[derive(Clone)]
+pub struct BoolGateTypeRegistry {
+    constructors: HashMap<u64, GateTypeConstructor>,
+}
+
+impl Default for BoolGateTypeRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BoolGateTypeRegistry {
+    pub fn new() -> Self {
+        let mut registry = Self {
+            constructors: HashMap::new(),
+        };
+        registry.register_unit(BoolGateTypeId::Input, InputGateType::new);
+        registry.register_unit(BoolGateTypeId::Output, OutputGateType::new);
+        registry.register_unit(BoolGateTypeId::Xor, XorGateType::new);
+        registry.register_unit(BoolGateTypeId::And, AndGateType::new);
+        registry.register_unit(BoolGateTypeId::Or, OrGateType::new);
+        registry.register_unit(BoolGateTypeId::Not, NotGateType::new);
+        registry.register_unit(BoolGateTypeId::False, || ConstantGateType::new(false));
+        registry.register_unit(BoolGateTypeId::True, || ConstantGateType::new(true));
+        registry
+            .register(BoolGateTypeId::Projection as u64, |params| {
+                let output_idx = u64::from_le_bytes(params.try_into().map_err(|_| {
+                    crate::Error::InvalidGateParams(
+                        BoolGateTypeId::Projection as u64,
+                        "the output index must be 8 bytes".to_string(),
+                    )
+                })?);
+                Ok(Box::new(ProjectionGateType::new(output_idx as usize)))
+            })
+            .unwrap();
+        registry
+            .register(BoolGateTypeId::Lut as u64, |params| {
+                Ok(Box::new(LutGateType::from_params(params)?))
+            })
+            .unwrap();
+        registry
+    }
+
+    /// Registers the constructor of the gate type whose id is `gate_type_id`.
+    /// It returns [`crate::Error::GateTypeAlreadyRegistered`] if the id is already used, e.g., by a built-in gate type.
+    pub fn register<F>(&mut self, gate_type_id: u64, constructor: F) -> Result<(), crate::Error>
+    where
+        F: Fn(&[u8]) -> Result<Box<dyn BoolGateType>, crate::Error> + Send + Sync + 'static,
+    {
+        if self.constructors.contains_key(&gate_type_id) {
+            return Err(crate::Error::GateTypeAlreadyRegistered(gate_type_id));
+        }
+        self.constructors
+            .insert(gate_type_id, Arc::new(constructor));
+        Ok(())
+    }
+
+    pub fn is_registered(&self, gate_type_id: u64) -> bool {
+        self.constructors.contains_key(&gate_type_id)
+    }
+
+    /// Returns a new instance of the gate type whose id is `gate_type_id` with `params`.
+    pub fn construct(
+        &self,
+        gate_type_id: u64,
+        params: &[u8],
+    ) -> Result<Box<dyn BoolGateType>, crate::Error> {
+        let constructor = self
+            .constructors
+            .get(&gate_type_id)
+            .ok_or(crate::Error::UnsupportedGateType(gate_type_id))?;
+        constructor(params)
+    }
+
+    /// Registers a gate type without parameters.
+    fn register_unit<T, F>(&mut self, gate_type_id: BoolGateTypeId, new: F)
+    where
+        T: BoolGateType,
+        F: Fn() -> T + Send + Sync + 'static,
+    {
+        let id = gate_type_id as u64;
+        self.register(id, move |params| {
+            if !params.is_empty() {
+                return Err(crate::Error::InvalidGateParams(
+                    id,
+                    "the gate type has no parameters".to_string(),
+                ));
+            }
+            Ok(Box::new(new()))
+        })
+        .unwrap();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::Rng;
+
+    const FULL_ADDER_ID: u64 = 100;
+    const PARITY_ID: u64 = 101;
+
+    /// Outputs the sum and the carry of three bits.
+    #[derive(Debug, Clone)]
+    struct FullAdderGateType {}
+
+    impl BoolGateType for FullAdderGateType {
+        fn eval(&self, input: &[bool]) -> Vec<bool> {
+            let sum = input.iter().filter(|bit| **bit).count();
+            vec![sum & 1 == 1, sum >= 2]
+        }
+
+        fn num_input(&self) -> usize {
+            3
+        }
+
+        fn num_output(&self) -> usize {
+            2
+        }
+
+        fn gate_type_id(&self) -> u64 {
+            FULL_ADDER_ID
+        }
+    }
+
+    /// Outputs the parity of `num_input` bits.
+    #[derive(Debug, Clone)]
+    struct ParityGateType {
+        num_input: usize,
+    }
+
+    impl BoolGateType for ParityGateType {
+        fn eval(&self, input: &[bool]) -> Vec<bool> {
+            vec![input.iter().fold(false, |acc, bit| acc ^ bit)]
+        }
+
+        fn num_input(&self) -> usize {
+            self.num_input
+        }
+
+        fn num_output(&self) -> usize {
+            1
+        }
+
+        fn gate_type_id(&self) -> u64 {
+            PARITY_ID
+        }
+
+        fn params(&self) -> Vec<u8> {
+            vec![self.num_input as u8]
+        }
+    }
+
+    fn registry() -> BoolGateTypeRegistry {
+        let mut registry = BoolGateTypeRegistry::new();
+        registry
+            .register(FULL_ADDER_ID, |_| Ok(Box::new(FullAdderGateType {})))
+            .unwrap();
+        registry
+            .register(PARITY_ID, |params| {
+                Ok(Box::new(ParityGateType {
+                    num_input: params[0] as usize,
+                }))
+            })
+            .unwrap();
+        registry
+    }
+
+    /// Builds a 4-bit ripple-carry adder of full adder gates and the parity of the sum.
+    fn adder_circuit() -> BoolCircuit {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let input = circuit_builder.input(8);
+        let mut carry = circuit_builder.constant(false);
+        let mut sum = vec![];
+        for idx in 0..4 {
+            let outputs = circuit_builder.gate_outputs(
+                Box::new(FullAdderGateType {}),
+                vec![input[idx].clone(), input[4 + idx].clone(), carry],
+            );
+            sum.push(outputs[0].clone());
+            carry = outputs[1].clone();
+        }
+        sum.push(carry);
+        let parity = circuit_builder.gate(Box::new(ParityGateType { num_input: 5 }), sum.clone());
+        sum.push(parity);
+        circuit_builder.output(sum)
+    }
+
+    fn expected_outputs(input: &[bool]) -> Vec<bool> {
+        let to_int = |bits: &[bool]| {
+            bits.iter()
+                .enumerate()
+                .fold(0, |acc, (idx, bit)| acc | ((*bit as usize) << idx))
+        };
+        let sum = to_int(&input[0..4]) + to_int(&input[4..8]);
+        let mut outputs = (0..5).map(|idx| (sum >> idx) & 1 == 1).collect_vec();
+        outputs.push(sum.count_ones() & 1 == 1);
+        outputs
+    }
+
+    #[test]
+    fn test_custom_gates_evaluation() {
+        let circuit = Arc::new(adder_circuit());
+        let mut plain_evaluator = PlainBoolCircuitEvaluator::new(circuit.clone());
+        let mut flat_evaluator = FlatBoolCircuitEvaluator::new(circuit.clone());
+        let mut rng = rand::thread_rng();
+        let inputs = (0..100)
+            .map(|_| (0..8).map(|_| rng.gen_bool(0.5)).collect_vec())
+            .collect_vec();
+        let batch_outputs = circuit.eval_batch(&inputs);
+        for (input, batch_output) in inputs.iter().zip(batch_outputs) {
+            let expected = expected_outputs(input);
+            assert_eq!(plain_evaluator.eval(input), expected);
+            assert_eq!(flat_evaluator.eval(input), expected);
+            assert_eq!(batch_output, expected);
+        }
+    }
+
+    #[test]
+    fn test_custom_gates_round_trip() {
+        let circuit = adder_circuit();
+        let registry = registry();
+        let decoded =
+            BoolCircuit::from_bytes_with_registry(&circuit.to_bytes(), &registry).unwrap();
+        let decoded_json =
+            BoolCircuit::from_json_with_registry(&circuit.to_json().unwrap(), &registry).unwrap();
+        assert_eq!(decoded.digest(), circuit.digest());
+        assert_eq!(decoded_json.digest(), circuit.digest());
+        let mut evaluator = PlainBoolCircuitEvaluator::new(Arc::new(decoded));
+        for bits in 0..256usize {
+            let input = (0..8).map(|idx| (bits >> idx) & 1 == 1).collect_vec();
+            assert_eq!(evaluator.eval(&input), expected_outputs(&input));
+        }
+        assert!(matches!(
+            BoolCircuit::from_bytes(&circuit.to_bytes()),
+            Err(crate::Error::UnsupportedGateType(FULL_ADDER_ID))
+        ));
+    }
+
+    #[test]
+    fn test_register_errors() {
+        let mut registry = registry();
+        assert!(registry.is_registered(FULL_ADDER_ID));
+        assert!(matches!(
+            registry.register(BoolGateTypeId::Xor as u64, |_| Ok(Box::new(
+                XorGateType::new()
+            ))),
+            Err(crate::Error::GateTypeAlreadyRegistered(2))
+        ));
+        assert!(matches!(
+            registry.register(FULL_ADDER_ID, |_| Ok(Box::new(FullAdderGateType {}))),
+            Err(crate::Error::GateTypeAlreadyRegistered(FULL_ADDER_ID))
+        ));
+        assert!(matches!(
+            registry.construct(BoolGateTypeId::Xor as u64, &[1]),
+            Err(crate::Error::InvalidGateParams(2, _))
+        ));
+        assert!(matches!(
+            registry.construct(BoolGateTypeId::Projection as u64, &[1]),
+            Err(crate::Error::InvalidGateParams(9, _))
+        ));
+        let projection = registry
+            .construct(BoolGateTypeId::Projection as u64, &3u64.to_le_bytes())
+            .unwrap();
+        assert_eq!(
+            projection
+                .downcast_ref::<ProjectionGateType>()
+                .unwrap()
+                .output_idx,
+            3
+        );
+        assert_eq!(projection.eval(&[true]), vec![true]);
+        assert_eq!(projection.eval(&[false]), vec![false]);
+    }
+}
@@ -0,0 +1,251 @@
+use super::*;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// The shape of [`BoolCircuit`], used to choose the parameters of PHFE.
+/// Only the gates reachable from the outputs are taken into account.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CircuitStats {
+    pub num_input: usize,
+    pub num_output: usize,
+    /// The number of the gates except for the input and output gates.
+    pub num_gates: usize,
+    /// The maximum number of gates on a path from an input to an output, excluding the input and output gates.
+    pub depth: usize,
+    /// The maximum number of AND/OR gates on a path from an input to an output.
+    pub multiplicative_depth: usize,
+    /// The number of gates of each gate type id, including the input and output gates.
+    pub gate_counts: BTreeMap<u64, usize>,
+    /// The maximum number of gates taking the same gate as an input.
+    pub max_fan_out: usize,
+    /// An upper bound of the degrees of the polynomials returned by [`BoolCircuit::to_polynomials`].
+    pub degree_bound: usize,
+}
+
+impl CircuitStats {
+    pub fn to_json(&self) -> Result<String, crate::Error> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, crate::Error> {
+        Ok(serde_json::from_str(s)?)
+    }
+}
+
+impl fmt::Display for CircuitStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "num_input: {}", self.num_input)?;
+        writeln!(f, "num_output: {}", self.num_output)?;
+        writeln!(f, "num_gates: {}", self.num_gates)?;
+        writeln!(f, "depth: {}", self.depth)?;
+        writeln!(f, "multiplicative_depth: {}", self.multiplicative_depth)?;
+        writeln!(f, "max_fan_out: {}", self.max_fan_out)?;
+        writeln!(f, "degree_bound: {}", self.degree_bound)?;
+        write!(f, "gate_counts:")?;
+        for (gate_type_id, count) in self.gate_counts.iter() {
+            match BoolGateTypeId::from_id(*gate_type_id) {
+                Some(id) => write!(f, "\n  {:?} ({}): {}", id, gate_type_id, count)?,
+                None => write!(f, "\n  {}: {}", gate_type_id, count)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The depths and the degree bound of a single gate.
+#[derive(Debug, Clone, Copy, Default)]
+struct GateShape {
+    depth: usize,
+    multiplicative_depth: usize,
+    degree: usize,
+}
+
+impl BoolCircuit {
+    /// Computes [`CircuitStats`] in a single pass over the gates sorted topologically.
+    /// A gate of a chip is analyzed through the chip, and a gate of any other unknown type is counted as one multiplicative level whose degree is the sum of the degrees of its inputs.
+    pub fn stats(&self) -> CircuitStats {
+        let gates = sort_gates(self);
+        let mut gate_counts = BTreeMap::<u64, usize>::new();
+        let mut fan_outs = HashMap::<GateId, usize>::new();
+        for gate in gates.iter() {
+            *gate_counts
+                .entry(gate.gate_type.gate_type_id())
+                .or_insert(0) += 1;
+            for input_gate in gate.inputs.iter() {
+                *fan_outs.entry(input_gate.gate_id).or_insert(0) += 1;
+            }
+        }
+        let output_shapes = self.output_shapes();
+        let num_gates = gates.len()
+            - gate_counts
+                .get(&(BoolGateTypeId::Input as u64))
+                .unwrap_or(&0)
+            - gate_counts
+                .get(&(BoolGateTypeId::Output as u64))
+                .unwrap_or(&0);
+        CircuitStats {
+            num_input: self.num_input(),
+            num_output: self.num_output(),
+            num_gates,
+            depth: output_shapes
+                .iter()
+                .map(|shape| shape.depth)
+                .max()
+                .unwrap_or(0),
+            multiplicative_depth: output_shapes
+                .iter()
+                .map(|shape| shape.multiplicative_depth)
+                .max()
+                .unwrap_or(0),
+            gate_counts,
+            max_fan_out: fan_outs.values().copied().max().unwrap_or(0),
+            degree_bound: output_shapes
+                .iter()
+                .map(|shape| shape.degree)
+                .max()
+                .unwrap_or(0),
+        }
+    }
+
+    /// Returns the shape of each output in terms of the inputs.
+    fn output_shapes(&self) -> Vec<GateShape> {
+        let mut shapes = HashMap::<GateId, GateShape>::new();
+        let mut chip_shapes = HashMap::<*const BoolCircuit, Vec<GateShape>>::new();
+        for gate in sort_gates(self) {
+            let inputs = gate
+                .inputs
+                .iter()
+                .map(|input_gate| shapes[&input_gate.gate_id])
+                .collect_vec();
+            let max_depth = inputs.iter().map(|shape| shape.depth).max().unwrap_or(0);
+            let max_multiplicative_depth = inputs
+                .iter()
+                .map(|shape| shape.multiplicative_depth)
+                .max()
+                .unwrap_or(0);
+            let sum_degree = inputs.iter().map(|shape| shape.degree).sum::<usize>();
+            let gate_type_id = gate.gate_type.gate_type_id();
+            let shape = match BoolGateTypeId::from_id(gate_type_id) {
+                Some(BoolGateTypeId::Input) => GateShape {
+                    depth: 0,
+                    multiplicative_depth: 0,
+                    degree: 1,
+                },
+                Some(BoolGateTypeId::Output) | Some(BoolGateTypeId::Projection) => inputs[0],
+                Some(BoolGateTypeId::False) | Some(BoolGateTypeId::True) => GateShape::default(),
+                Some(BoolGateTypeId::Not) => GateShape {
+                    depth: max_depth + 1,
+                    ..inputs[0]
+                },
+                Some(BoolGateTypeId::Xor) => GateShape {
+                    depth: max_depth + 1,
+                    multiplicative_depth: max_multiplicative_depth,
+                    degree: sum_degree,
+                },
+                Some(BoolGateTypeId::ChipRef) => {
+                    let chip_ref = gate.gate_type.downcast_ref::<ChipRefType>().unwrap();
+                    let chip_shape = chip_shapes
+                        .entry(Arc::as_ptr(&chip_ref.chip))
+                        .or_insert_with(|| chip_ref.chip.output_shapes())[chip_ref.output_idx];
+                    // Each input of the chip has degree at most the maximum degree of the inputs of the gate.
+                    let max_degree = inputs.iter().map(|shape| shape.degree).max().unwrap_or(0);
+                    GateShape {
+                        depth: max_depth + chip_shape.depth,
+                        multiplicative_depth: max_multiplicative_depth
+                            + chip_shape.multiplicative_depth,
+                        degree: chip_shape.degree * max_degree,
+                    }
+                }
+                _ => GateShape {
+                    depth: max_depth + 1,
+                    multiplicative_depth: max_multiplicative_depth + 1,
+                    degree: sum_degree,
+                },
+            };
+            // Every polynomial is multilinear in the inputs.
+            let shape = GateShape {
+                degree: shape.degree.min(self.num_input()),
+                ..shape
+            };
+            shapes.insert(gate.gate_id, shape);
+        }
+        self.output_gates
+            .iter()
+            .map(|gate| shapes[&gate.gate_id])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_bn254::Fr;
+
+    fn test_circuit() -> BoolCircuit {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let input = circuit_builder.input(4);
+        let or = circuit_builder.or(&input[0], &input[1]);
+        let and = circuit_builder.and(&input[1], &input[2]);
+        let xor = circuit_builder.xor(&or, &and);
+        let not = circuit_builder.not(&xor);
+        let and2 = circuit_builder.and(&not, &input[1]);
+        circuit_builder.output(vec![xor, and2, input[3].clone()])
+    }
+
+    #[test]
+    fn test_stats() {
+        let circuit = test_circuit();
+        let stats = circuit.stats();
+        assert_eq!(stats.num_input, 4);
+        assert_eq!(stats.num_output, 3);
+        assert_eq!(stats.num_gates, 5);
+        assert_eq!(stats.depth, 4);
+        assert_eq!(stats.multiplicative_depth, 2);
+        assert_eq!(
+            stats.gate_counts,
+            BTreeMap::from([(0, 4), (1, 3), (2, 1), (3, 2), (4, 1), (5, 1)])
+        );
+        // `x1` is used by the OR gate and the two AND gates.
+        assert_eq!(stats.max_fan_out, 3);
+        assert_eq!(stats.degree_bound, 4);
+        let max_degree = circuit
+            .to_polynomials::<Fr>(None)
+            .unwrap()
+            .iter()
+            .map(|poly| poly.degree())
+            .max()
+            .unwrap();
+        assert!(max_degree <= stats.degree_bound);
+    }
+
+    #[test]
+    fn test_stats_with_chips_and_constants() {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let chip = circuit_builder.register_chip(test_circuit());
+        let input = circuit_builder.input(4);
+        let outputs = circuit_builder.call_chip(&input, &chip);
+        let one = circuit_builder.constant(true);
+        let and = circuit_builder.and(&outputs[0], &one);
+        let circuit = circuit_builder.output(vec![and, outputs[1].clone()]);
+        let stats = circuit.stats();
+        let flattened_stats = circuit.flatten_chips().stats();
+        assert_eq!(stats.depth, 4);
+        assert_eq!(stats.depth, flattened_stats.depth);
+        assert_eq!(stats.multiplicative_depth, 2);
+        assert_eq!(
+            stats.multiplicative_depth,
+            flattened_stats.multiplicative_depth
+        );
+        assert_eq!(stats.degree_bound, flattened_stats.degree_bound);
+    }
+
+    #[test]
+    fn test_stats_json_and_display() {
+        let stats = test_circuit().stats();
+        let decoded = CircuitStats::from_json(&stats.to_json().unwrap()).unwrap();
+        assert_eq!(decoded, stats);
+        let display = stats.to_string();
+        assert!(display.contains("multiplicative_depth: 2"));
+        assert!(display.contains("And (3): 2"));
+    }
+}
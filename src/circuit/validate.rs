@@ -0,0 +1,208 @@
+use super::*;
+
+impl BoolCircuit {
+    /// Checks that the circuit can be evaluated safely, i.e., for every gate reachable from the outputs,
+    /// - the number of its inputs equals [`BoolGateType::num_input`] of its gate type,
+    /// - it is an input gate if and only if its id is less than `num_input`, and it is a [`ProjectionGateType`] gate only if its id is that of the output it refers to,
+    /// - its ids, one per output, are less than `num_gates` and not used by any other gate,
+    /// - it does not depend on a gate with the same id as itself.
+    ///
+    /// The chips referenced by [`ChipRefType`] gates are validated recursively.
+    ///
+    /// The ids are not required to be dense, i.e., to cover `0..num_gates`, because [`BoolCircuitBuilder`] leaves gaps in valid circuits:
+    /// [`BoolCircuitBuilder::call_chip`] reserves the ids of the gates inside the chip, and the gates not reachable from the outputs, such as unused inputs or gates removed by an optimization, keep their ids.
+    /// The evaluators allocate one wire per id below `num_gates`, so a gap only leaves a wire unused.
+    pub fn validate(&self) -> Result<(), crate::Error> {
+        let mut validated_chips = HashSet::<*const BoolCircuit>::new();
+        self.validate_gates(&mut validated_chips)
+    }
+
+    fn validate_gates(
+        &self,
+        validated_chips: &mut HashSet<*const BoolCircuit>,
+    ) -> Result<(), crate::Error> {
+        let num_ids = self.num_gates.max(self.num_input);
+        let mut owners = HashMap::<usize, *const BoolGate>::new();
+        for gate in self.reachable_gates()? {
+            let gate_id = gate.gate_id.id;
+            let gate_type = &gate.gate_type;
+            if gate.inputs.len() != gate_type.num_input() {
+                return Err(crate::Error::InvalidGateArity(
+                    gate_id,
+                    gate_type.num_input(),
+                    gate.inputs.len(),
+                ));
+            }
+            let gate_type_id = gate_type.gate_type_id();
+            if (gate_type_id == BoolGateTypeId::Input as u64) != (gate_id < self.num_input) {
+                return Err(crate::Error::UnexpectedGateType(gate_id, gate_type_id));
+            }
+            if let Some(projection) = gate_type.downcast_ref::<ProjectionGateType>() {
+                let parent = &gate.inputs[0];
+                if projection.output_idx >= parent.gate_type.num_output()
+                    || gate_id != parent.gate_id.id + projection.output_idx
+                {
+                    return Err(crate::Error::UnexpectedGateType(gate_id, gate_type_id));
+                }
+                // The id is claimed by the parent.
+                continue;
+            }
+            if let Some(chip_ref) = gate_type.downcast_ref::<ChipRefType>() {
+                if validated_chips.insert(Arc::as_ptr(&chip_ref.chip)) {
+                    chip_ref.chip.validate_gates(validated_chips)?;
+                }
+            }
+            for id in gate_id..gate_id + gate_type.num_output() {
+                if id >= num_ids {
+                    return Err(crate::Error::GateIdOutOfRange(id, num_ids));
+                }
+                if owners.insert(id, Arc::as_ptr(&gate)).is_some() {
+                    return Err(crate::Error::DuplicateGateId(id));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns all gates reachable from the output gates, each of which appears only once.
+    /// Unlike [`sort_gates`], the gates are distinguished by their pointers, and [`crate::Error::CyclicCircuit`] is returned if a gate depends on another gate with the same id.
+    fn reachable_gates(&self) -> Result<Vec<Arc<BoolGate>>, crate::Error> {
+        let mut visited = HashSet::<*const BoolGate>::new();
+        let mut gates = vec![];
+        // The ids of the gates on the path from the output gate being searched.
+        let mut path_ids = HashSet::<GateId>::new();
+        let mut stack = Vec::<(Arc<BoolGate>, usize)>::new();
+        for output_gate in self.output_gates.iter() {
+            if !visited.insert(Arc::as_ptr(output_gate)) {
+                continue;
+            }
+            path_ids.insert(output_gate.gate_id);
+            stack.push((output_gate.clone(), 0));
+            while let Some((gate, input_idx)) = stack.pop() {
+                if input_idx == gate.inputs.len() {
+                    path_ids.remove(&gate.gate_id);
+                    gates.push(gate);
+                    continue;
+                }
+                let input_gate = gate.inputs[input_idx].clone();
+                stack.push((gate, input_idx + 1));
+                if path_ids.contains(&input_gate.gate_id) {
+                    return Err(crate::Error::CyclicCircuit(input_gate.gate_id.id));
+                }
+                if visited.insert(Arc::as_ptr(&input_gate)) {
+                    path_ids.insert(input_gate.gate_id);
+                    stack.push((input_gate, 0));
+                }
+            }
+        }
+        Ok(gates)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn gate(
+        id: usize,
+        gate_type: Box<dyn BoolGateType>,
+        inputs: Vec<Arc<BoolGate>>,
+    ) -> Arc<BoolGate> {
+        Arc::new(BoolGate::new(GateId::new(id), gate_type, inputs))
+    }
+
+    fn inputs() -> Vec<Arc<BoolGate>> {
+        (0..2)
+            .map(|id| gate(id, Box::new(InputGateType::new()), vec![]))
+            .collect()
+    }
+
+    fn output(id: usize, input: Arc<BoolGate>) -> BoolCircuit {
+        BoolCircuit::new(
+            vec![gate(id, Box::new(OutputGateType::new()), vec![input])],
+            2,
+        )
+    }
+
+    #[test]
+    fn test_valid_circuits() {
+        let mut circuit_builder = BoolCircuitBuilder::new();
+        let chip = {
+            let mut chip_builder = BoolCircuitBuilder::new();
+            let input = chip_builder.input(2);
+            let and = chip_builder.and(&input[0], &input[1]);
+            chip_builder.output(vec![and])
+        };
+        let chip = circuit_builder.register_chip(chip);
+        let input = circuit_builder.input(3);
+        let outputs = circuit_builder.call_chip(&input[0..2], &chip);
+        let lut = circuit_builder
+            .lut_from_fn(&[outputs[0].clone(), input[2].clone()], 2, |row| row ^ 3)
+            .unwrap();
+        let circuit = Arc::new(circuit_builder.output(lut));
+        assert!(circuit.validate().is_ok());
+        assert!(circuit.flatten_chips().validate().is_ok());
+
+        let mut evaluator = PlainBoolCircuitEvaluator::new(circuit.clone());
+        assert_eq!(
+            evaluator.try_eval(&[true, true, false]).unwrap(),
+            vec![false, true]
+        );
+        assert!(matches!(
+            evaluator.try_eval(&[true, true]),
+            Err(crate::Error::InvalidInputLength(3, 2))
+        ));
+        let mut evaluator = FlatBoolCircuitEvaluator::try_new(circuit).unwrap();
+        assert_eq!(
+            evaluator.try_eval(&[true, false, true]).unwrap(),
+            vec![true, false]
+        );
+        assert!(matches!(
+            evaluator.try_eval(&[true, false, true, false]),
+            Err(crate::Error::InvalidInputLength(3, 4))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_circuits() {
+        let input = inputs();
+        let xor = gate(2, Box::new(XorGateType::new()), vec![input[0].clone()]);
+        assert!(matches!(
+            output(3, xor).validate(),
+            Err(crate::Error::InvalidGateArity(2, 2, 1))
+        ));
+
+        let fake_input = gate(2, Box::new(InputGateType::new()), vec![]);
+        let circuit = Arc::new(output(3, fake_input));
+        assert!(matches!(
+            circuit.validate(),
+            Err(crate::Error::UnexpectedGateType(2, 0))
+        ));
+        let mut evaluator = PlainBoolCircuitEvaluator::new(circuit);
+        assert!(matches!(
+            evaluator.try_eval(&[true, false]),
+            Err(crate::Error::UnexpectedGateType(2, 0))
+        ));
+
+        let not = gate(2, Box::new(NotGateType::new()), vec![input[0].clone()]);
+        let not2 = gate(2, Box::new(NotGateType::new()), vec![input[1].clone()]);
+        let and = gate(3, Box::new(AndGateType::new()), vec![not, not2]);
+        assert!(matches!(
+            output(4, and).validate(),
+            Err(crate::Error::DuplicateGateId(2))
+        ));
+
+        let not = gate(2, Box::new(NotGateType::new()), vec![input[0].clone()]);
+        let not2 = gate(2, Box::new(NotGateType::new()), vec![not]);
+        assert!(matches!(
+            output(3, not2).validate(),
+            Err(crate::Error::CyclicCircuit(2))
+        ));
+
+        let not = gate(10, Box::new(NotGateType::new()), vec![input[0].clone()]);
+        assert!(matches!(
+            output(3, not).validate(),
+            Err(crate::Error::GateIdOutOfRange(10, 4))
+        ));
+    }
+}
@@ -1,3 +1,4 @@
+use crate::poly::Variable;
 use serde_json;
 use thiserror;
 
@@ -9,6 +10,56 @@ pub enum Error {
     InvalidMonomialString(String, String),
     #[error("The given string {0} is invalid for polynomial. Reason: {1}")]
     InvalidPolynomialString(String, String),
+    #[error(
+        "The number of polynomials {0} does not match num_private_vars1 * num_private_vars2 = {1}."
+    )]
+    InvalidNumPolys(usize, usize),
+    #[error("The variable {0:?} is not a public variable whose index is less than {1}.")]
+    InvalidPublicVariable(Variable, usize),
+    #[error("The variable {0:?} is not a private variable whose index is less than {1}.")]
+    InvalidPrivateVariable(Variable, usize),
+    #[error("The monomial {0} is not the product of one first private variable, one second private variable and public variables.")]
+    NonBilinearMonomial(String),
+    #[error("The {1} {0:?} variables are required, but only {2} values are given.")]
+    MissingVariableValues(crate::poly::VariableType, usize, usize),
+    #[error("The edge from the node {0} to the node {1} is invalid for the ABP. Reason: {2}")]
+    InvalidAbpEdge(usize, usize, String),
+    #[error("The gate type id {0} is not supported.")]
+    UnsupportedGateType(u64),
+    #[error(
+        "The polynomial of the gate {0} has degree {1}, which exceeds the maximum degree {2}."
+    )]
+    PolynomialDegreeExceeded(usize, usize, usize),
+    #[error("The gate type id {0} is already registered.")]
+    GateTypeAlreadyRegistered(u64),
+    #[error("The parameters of the gate type {0} are invalid. Reason: {1}")]
+    InvalidGateParams(u64, String),
+    #[error("The encoded circuit is invalid. Reason: {0}")]
+    InvalidEncodedCircuit(String),
+    #[error("The Bristol circuit is invalid at line {0}. Reason: {1}")]
+    InvalidBristolCircuit(usize, String),
+    #[error("The optimization pass {0} changed the outputs of the circuit.")]
+    NonEquivalentCircuit(String),
+    #[error("The number of the given inputs {1} does not match the number of the inputs of the circuit {0}.")]
+    InvalidInputLength(usize, usize),
+    #[error("The config of the circuit is invalid. Reason: {0}")]
+    InvalidCircuitConfig(String),
+    #[error("The input index {0} is not less than the number of the inputs {1}.")]
+    InputIndexOutOfRange(usize, usize),
+    #[error("The output index {0} is not less than the number of the outputs {1}.")]
+    OutputIndexOutOfRange(usize, usize),
+    #[error("The gate {0} must have {1} inputs, but it has {2} inputs.")]
+    InvalidGateArity(usize, usize, usize),
+    #[error("The gate {0} cannot have the gate type id {1} at its id.")]
+    UnexpectedGateType(usize, u64),
+    #[error("The gate id {0} is used by more than one gate.")]
+    DuplicateGateId(usize),
+    #[error("The gate id {0} is not less than the number of gates {1}.")]
+    GateIdOutOfRange(usize, usize),
+    #[error("The circuit has a cycle through the gate id {0}.")]
+    CyclicCircuit(usize),
+    #[error(transparent)]
+    BincodeError(#[from] bincode::Error),
     #[error(transparent)]
     SerdeJsonError(#[from] serde_json::Error),
     #[error(transparent)]
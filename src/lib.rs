@@ -1,5 +1,5 @@
-mod circuit;
+pub mod circuit;
 mod error;
-mod phfe;
-mod poly;
+pub mod phfe;
+pub mod poly;
 pub use error::*;
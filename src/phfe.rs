@@ -1,6 +1,10 @@
+pub mod abp;
 pub mod func;
 pub mod linear_phfe;
+pub mod partial_garbling;
 pub mod utils;
+use crate::poly::CompiledPolynomials;
+use crate::Error;
 use ark_ec::pairing::PairingOutput;
 use ark_ec::{pairing::Pairing, Group};
 use ark_ff::UniformRand;
@@ -8,13 +12,10 @@ pub use func::PhfeFunc;
 use linear_phfe::*;
 use nalgebra::{DMatrix, DVector, Dyn, Matrix, U1};
 use rand::Rng;
-use std::collections::HashMap;
 use std::marker::PhantomData;
 pub use utils::PhfeElement;
 use utils::*;
 
-use crate::poly::{Variable, VariableType};
-
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct PhfeMpk<C: Pairing> {
     pub a1_g1_matrix: DMatrix<PhfeElement<C>>,
@@ -178,35 +179,23 @@ impl<C: Pairing> Phfe<C> {
         PhfeFsk { linear_fsk }
     }
 
+    /// Decrypts `ct` with `fsk`, returning [`Error::MissingVariableValues`] if `ct` has fewer public values than the variables of `f`.
     pub fn dec(
         &self,
         ct: &PhfeCt<C>,
         f: &PhfeFunc<C::ScalarField>,
         fsk: &PhfeFsk<C>,
-    ) -> PairingOutput<C> {
-        let linear_dec = self.linear_phfe.dec(&ct.linear_ct, f, &fsk.linear_fsk);
-        println!("linear_dec: {:?}", linear_dec);
+    ) -> Result<PairingOutput<C>, Error> {
+        let linear_dec = self.linear_phfe.dec(&ct.linear_ct, f, &fsk.linear_fsk)?;
         let y1y2 = ct.y1_vec.kronecker(&ct.y2_vec);
-        let mut x_assignment = HashMap::<Variable, C::ScalarField>::new();
-        for (idx, val) in ct.linear_ct.x.into_iter().enumerate() {
-            x_assignment.insert(
-                Variable {
-                    index: idx as u32,
-                    variable_type: VariableType::Public,
-                },
-                *val,
-            );
-        }
+        let fx_vec = CompiledPolynomials::new(&f.polys).eval(ct.linear_ct.x.as_slice(), &[])?;
         let fx = DVector::from_iterator(
             y1y2.len(),
-            f.polys
-                .iter()
-                .map(|f| PhfeElement::Scalar(f.eval(&x_assignment))),
+            fx_vec.into_iter().map(|fx| PhfeElement::Scalar(fx)),
         );
         let y1y2f = y1y2.transpose() * &fx;
-        println!("y1y2f: {:?}", y1y2f);
         if let PhfeElement::Gt(y1y2f) = y1y2f[(0, 0)] {
-            y1y2f - linear_dec
+            Ok(y1y2f - linear_dec)
         } else {
             panic!("Unexpected element type")
         }
@@ -216,9 +205,12 @@ impl<C: Pairing> Phfe<C> {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::poly::{Polynomial, Variable, VariableType};
     use ark_bn254::{Bn254, Fr};
     use ark_std::{end_timer, start_timer};
     use num_traits::{One, Zero};
+    use std::collections::HashMap;
+    use std::str::FromStr;
 
     #[test]
     fn test_valid_case1() {
@@ -237,7 +229,7 @@ mod test {
         let z2 = DVector::from_fn(3, |_, _| Fr::from(rng.gen_range(0..=1)));
         let ct = phfe.enc(&mpk, &x, &z1, &z2, &mut rng);
         let fsk = phfe.gen_fsk(&msk, &func, &mut rng);
-        let out_gt = phfe.dec(&ct, &func, &fsk);
+        let out_gt = phfe.dec(&ct, &func, &fsk).unwrap();
         // let out = if out_gt == PairingOutput::generator() {
         //     Fr::one()
         // } else if out_gt == PairingOutput::zero() {
@@ -283,7 +275,7 @@ mod test {
         let z2 = DVector::from_fn(5, |_, _| Fr::from(rng.gen_range(0..=1)));
         let ct = phfe.enc(&mpk, &x, &z1, &z2, &mut rng);
         let fsk = phfe.gen_fsk(&msk, &func, &mut rng);
-        let out_gt = phfe.dec(&ct, &func, &fsk);
+        let out_gt = phfe.dec(&ct, &func, &fsk).unwrap();
         let expected_out = {
             let mut x_assignment = HashMap::<Variable, Fr>::new();
             for (idx, val) in x.iter().enumerate() {
@@ -306,6 +298,104 @@ mod test {
         assert_eq!(out_gt, PairingOutput::generator() * expected_out);
     }
 
+    #[test]
+    fn test_valid_case_from_polys() {
+        let polys = vec![
+            "x1 + x2 + 1",
+            "x0*x1 + x1*x2",
+            "x0*x2 + x1 + x2 + 1",
+            "x0*x1 + x1*x2",
+            "x0*x2 + x0 + x1",
+            "x0*x1 + x1*x2 + 1",
+        ]
+        .into_iter()
+        .map(|s| Polynomial::from_str(s).unwrap())
+        .collect();
+        let func: PhfeFunc<Fr> = PhfeFunc::from_polys(3, 2, 3, polys).unwrap();
+        let phfe = Phfe::<Bn254>::new(
+            func.num_public_vars,
+            func.num_private_vars1,
+            func.num_private_vars2,
+            2,
+        );
+        let mut rng = rand::thread_rng();
+        let (mpk, msk) = phfe.setup(&mut rng);
+        let x = DVector::from_fn(3, |_, _| Fr::from(rng.gen_range(0..=1)));
+        let z1 = DVector::from_fn(2, |_, _| Fr::from(rng.gen_range(0..=1)));
+        let z2 = DVector::from_fn(3, |_, _| Fr::from(rng.gen_range(0..=1)));
+        let ct = phfe.enc(&mpk, &x, &z1, &z2, &mut rng);
+        let fsk = phfe.gen_fsk(&msk, &func, &mut rng);
+        let out_gt = phfe.dec(&ct, &func, &fsk).unwrap();
+        let expected_out = {
+            let mut x_assignment = HashMap::<Variable, Fr>::new();
+            for (idx, val) in x.iter().enumerate() {
+                x_assignment.insert(
+                    Variable {
+                        index: idx as u32,
+                        variable_type: VariableType::Public,
+                    },
+                    *val,
+                );
+            }
+            let z1z2 = z1.kronecker(&z2);
+            let fx = DVector::from_iterator(
+                z1z2.len(),
+                func.polys.iter().map(|f| f.eval(&x_assignment)),
+            );
+            (z1z2.transpose() * &fx)[(0, 0)]
+        };
+        assert_eq!(out_gt, PairingOutput::generator() * expected_out);
+        // A ciphertext missing a public value is rejected rather than decrypted.
+        let mut short_ct = ct.clone();
+        short_ct.linear_ct.x = x.rows(0, 2).into_owned();
+        assert!(matches!(
+            phfe.dec(&short_ct, &func, &fsk),
+            Err(Error::MissingVariableValues(VariableType::Public, 3, 2))
+        ));
+    }
+
+    #[test]
+    fn test_valid_case_from_mixed_poly() {
+        let poly = Polynomial::from_str(
+            "z0*z2*(x0*x1 + 1) + z1*z3*(x1 + x2) - 2*z0*z4*x2 + z1*z2*x0*x2 + z0*z3",
+        )
+        .unwrap();
+        let func: PhfeFunc<Fr> = PhfeFunc::from_mixed_poly(3, 2, 3, &poly).unwrap();
+        let phfe = Phfe::<Bn254>::new(
+            func.num_public_vars,
+            func.num_private_vars1,
+            func.num_private_vars2,
+            2,
+        );
+        let mut rng = rand::thread_rng();
+        let (mpk, msk) = phfe.setup(&mut rng);
+        let x = DVector::from_fn(3, |_, _| Fr::from(rng.gen_range(0..=1)));
+        let z1 = DVector::from_fn(2, |_, _| Fr::from(rng.gen_range(0..=1)));
+        let z2 = DVector::from_fn(3, |_, _| Fr::from(rng.gen_range(0..=1)));
+        let ct = phfe.enc(&mpk, &x, &z1, &z2, &mut rng);
+        let fsk = phfe.gen_fsk(&msk, &func, &mut rng);
+        let out_gt = phfe.dec(&ct, &func, &fsk).unwrap();
+        let expected_out = {
+            let public = x.iter().enumerate().map(|(idx, val)| {
+                let variable = Variable {
+                    index: idx as u32,
+                    variable_type: VariableType::Public,
+                };
+                (variable, *val)
+            });
+            let private = z1.iter().chain(z2.iter()).enumerate().map(|(idx, val)| {
+                let variable = Variable {
+                    index: idx as u32,
+                    variable_type: VariableType::Private,
+                };
+                (variable, *val)
+            });
+            poly.eval(&public.chain(private).collect())
+        };
+        assert_eq!(func.eval(&x, &z1, &z2).unwrap(), expected_out);
+        assert_eq!(out_gt, PairingOutput::generator() * expected_out);
+    }
+
     #[test]
     fn test_valid_case3() {
         let func_json = include_str!("./phfe/tests/test_phfe3.json");
@@ -330,7 +420,7 @@ mod test {
         let fsk = phfe.gen_fsk(&msk, &func, &mut rng);
         end_timer!(fsk_timer);
         let dec_timer = start_timer!(|| "decryption");
-        let out_gt = phfe.dec(&ct, &func, &fsk);
+        let out_gt = phfe.dec(&ct, &func, &fsk).unwrap();
         end_timer!(dec_timer);
         let expected_out = {
             let mut x_assignment = HashMap::<Variable, Fr>::new();
@@ -0,0 +1,545 @@
+use crate::poly::{Monomial, Polynomial, Variable, VariableType};
+use crate::Error;
+use ark_ff::PrimeField;
+use itertools::Itertools;
+use nalgebra::DMatrix;
+use petgraph::algo::{has_path_connecting, toposort};
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::visit::EdgeRef;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::{Add, AddAssign};
+
+/// An affine form `constant + sum_k coeffs[k] * x_k` in the public variables, used as an edge label of [`Abp`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AffineForm<F: PrimeField> {
+    pub constant: F,
+    pub coeffs: BTreeMap<u32, F>,
+}
+
+impl<F: PrimeField> AffineForm<F> {
+    pub fn new(constant: F, coeffs: BTreeMap<u32, F>) -> Self {
+        Self { constant, coeffs }
+    }
+
+    pub fn zero() -> Self {
+        Self::constant(F::zero())
+    }
+
+    pub fn constant(constant: F) -> Self {
+        Self {
+            constant,
+            coeffs: BTreeMap::new(),
+        }
+    }
+
+    pub fn variable(index: u32, coeff: F) -> Self {
+        Self {
+            constant: F::zero(),
+            coeffs: BTreeMap::from([(index, coeff)]),
+        }
+    }
+
+    pub fn coeff(&self, index: u32) -> F {
+        self.coeffs.get(&index).copied().unwrap_or(F::zero())
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.constant.is_zero() && self.coeffs.values().all(|coeff| coeff.is_zero())
+    }
+
+    pub fn eval(&self, assignment: &HashMap<Variable, F>) -> F {
+        let mut result = self.constant;
+        for (index, coeff) in self.coeffs.iter() {
+            let variable = Variable {
+                index: *index,
+                variable_type: VariableType::Public,
+            };
+            result += assignment[&variable] * coeff;
+        }
+        result
+    }
+}
+
+impl<F: PrimeField> Add for AffineForm<F> {
+    type Output = Self;
+
+    fn add(mut self, rhs: Self) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+impl<F: PrimeField> AddAssign for AffineForm<F> {
+    fn add_assign(&mut self, rhs: Self) {
+        self.constant += rhs.constant;
+        for (index, coeff) in rhs.coeffs.into_iter() {
+            *self.coeffs.entry(index).or_insert(F::zero()) += coeff;
+        }
+    }
+}
+
+/// A polynomial over the public variables, keyed by the sorted variable indices of each monomial.
+type SparsePoly<F> = BTreeMap<Vec<u32>, F>;
+
+/// An arithmetic branching program (ABP) with a single start node and one end node per output.
+/// The i-th output is the sum over all paths from the start node to the i-th end node of the product of the edge labels.
+/// The fields are private so that the graph stays acyclic, without edges into the start node or out of the end nodes, as checked by [`Abp::add_edge`].
+#[derive(Debug, Clone)]
+pub struct Abp<F: PrimeField> {
+    num_public_vars: usize,
+    graph: DiGraph<(), AffineForm<F>>,
+    start_node: NodeIndex,
+    end_nodes: Vec<NodeIndex>,
+}
+
+impl<F: PrimeField> Abp<F> {
+    pub fn new(num_public_vars: usize, num_outputs: usize) -> Self {
+        let mut graph = DiGraph::new();
+        let start_node = graph.add_node(());
+        let end_nodes = (0..num_outputs).map(|_| graph.add_node(())).collect_vec();
+        Self {
+            num_public_vars,
+            graph,
+            start_node,
+            end_nodes,
+        }
+    }
+
+    /// Builds an ABP whose i-th output is `polys[i]`.
+    /// A monomial `c * x_{v1} * ... * x_{vk}` of the i-th polynomial is the path `start -(c * x_{v1})-> N(v2..vk) -(x_{v2})-> ... -(x_{vk})-> end_i`, where the suffix nodes `N(..)` are shared among the monomials of the same polynomial.
+    pub fn from_polynomials(
+        num_public_vars: usize,
+        polys: &[Polynomial<F>],
+    ) -> Result<Self, Error> {
+        let mut abp = Self::new(num_public_vars, polys.len());
+        let mut node_of_suffix = HashMap::<(usize, Vec<u32>), NodeIndex>::new();
+        for (poly_idx, poly) in polys.iter().enumerate() {
+            let end_node = abp.end_nodes[poly_idx];
+            for (indices, coeff) in to_sparse_poly(poly, num_public_vars)? {
+                if indices.is_empty() {
+                    abp.insert_edge(abp.start_node, end_node, AffineForm::constant(coeff))?;
+                    continue;
+                }
+                // Create the chain of suffix nodes from the end node backwards.
+                let mut next_node = end_node;
+                for suffix_start in (1..indices.len()).rev() {
+                    let key = (poly_idx, indices[suffix_start..].to_vec());
+                    if let Some(node) = node_of_suffix.get(&key) {
+                        next_node = *node;
+                        continue;
+                    }
+                    let node = abp.add_node();
+                    abp.insert_edge(
+                        node,
+                        next_node,
+                        AffineForm::variable(indices[suffix_start], F::one()),
+                    )?;
+                    node_of_suffix.insert(key, node);
+                    next_node = node;
+                }
+                abp.insert_edge(
+                    abp.start_node,
+                    next_node,
+                    AffineForm::variable(indices[0], coeff),
+                )?;
+            }
+        }
+        Ok(abp)
+    }
+
+    pub fn num_public_vars(&self) -> usize {
+        self.num_public_vars
+    }
+
+    pub fn graph(&self) -> &DiGraph<(), AffineForm<F>> {
+        &self.graph
+    }
+
+    pub fn start_node(&self) -> NodeIndex {
+        self.start_node
+    }
+
+    pub fn end_nodes(&self) -> &[NodeIndex] {
+        &self.end_nodes
+    }
+
+    pub fn add_node(&mut self) -> NodeIndex {
+        self.graph.add_node(())
+    }
+
+    /// Adds `label` to the label of the edge from `from` to `to`, creating the edge if it does not exist.
+    /// It returns [`Error::InvalidPublicVariable`] if `label` refers to a variable whose index is not less than `num_public_vars`,
+    /// and [`Error::InvalidAbpEdge`] if a node does not exist, `to` is the start node, `from` is an end node or the edge creates a cycle.
+    pub fn add_edge(
+        &mut self,
+        from: NodeIndex,
+        to: NodeIndex,
+        label: AffineForm<F>,
+    ) -> Result<(), Error> {
+        let invalid =
+            |reason: &str| Error::InvalidAbpEdge(from.index(), to.index(), reason.to_string());
+        if from.index() >= self.num_nodes() || to.index() >= self.num_nodes() {
+            return Err(invalid("the node does not exist"));
+        }
+        if to == self.start_node {
+            return Err(invalid("the edge goes into the start node"));
+        }
+        if self.end_nodes.contains(&from) {
+            return Err(invalid("the edge goes out of an end node"));
+        }
+        if self.graph.find_edge(from, to).is_none()
+            && has_path_connecting(&self.graph, to, from, None)
+        {
+            return Err(invalid("the edge creates a cycle"));
+        }
+        self.insert_edge(from, to, label)
+    }
+
+    /// Same as [`Abp::add_edge`] without checking that the edge keeps the graph acyclic, for the edges of [`Abp::from_polynomials`] that go into existing nodes from new ones.
+    fn insert_edge(
+        &mut self,
+        from: NodeIndex,
+        to: NodeIndex,
+        label: AffineForm<F>,
+    ) -> Result<(), Error> {
+        if let Some(index) = label
+            .coeffs
+            .keys()
+            .find(|index| **index as usize >= self.num_public_vars)
+        {
+            let variable = Variable {
+                index: *index,
+                variable_type: VariableType::Public,
+            };
+            return Err(Error::InvalidPublicVariable(variable, self.num_public_vars));
+        }
+        match self.graph.find_edge(from, to) {
+            Some(edge) => self.graph[edge] += label,
+            None => {
+                self.graph.add_edge(from, to, label);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn num_outputs(&self) -> usize {
+        self.end_nodes.len()
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.graph.node_count()
+    }
+
+    pub fn num_edges(&self) -> usize {
+        self.graph.edge_count()
+    }
+
+    /// Returns the nodes in the order of the rows of the `L_x` matrix, i.e., the start node first, the end nodes last and the other nodes in between.
+    pub fn node_order(&self) -> Vec<NodeIndex> {
+        let end_nodes = self.end_nodes.iter().collect::<HashSet<_>>();
+        let middle_nodes = self
+            .graph
+            .node_indices()
+            .filter(|node| *node != self.start_node && !end_nodes.contains(node));
+        vec![self.start_node]
+            .into_iter()
+            .chain(middle_nodes)
+            .chain(self.end_nodes.iter().copied())
+            .collect()
+    }
+
+    /// Evaluates every output of the ABP on `assignment` of the public variables.
+    pub fn eval(&self, assignment: &HashMap<Variable, F>) -> Vec<F> {
+        let mut path_sums = HashMap::<NodeIndex, F>::new();
+        path_sums.insert(self.start_node, F::one());
+        for node in self.sorted_nodes() {
+            let from_sum = path_sums.get(&node).copied().unwrap_or(F::zero());
+            if from_sum.is_zero() {
+                continue;
+            }
+            for edge in self.graph.edges(node) {
+                *path_sums.entry(edge.target()).or_insert(F::zero()) +=
+                    from_sum * edge.weight().eval(assignment);
+            }
+        }
+        self.end_nodes
+            .iter()
+            .map(|node| path_sums.get(node).copied().unwrap_or(F::zero()))
+            .collect()
+    }
+
+    /// Returns the polynomial computed at every node, i.e., the sum of the labels of the paths from the start node to it, in the order of [`Abp::node_order`].
+    pub fn path_polys(&self) -> Vec<Polynomial<F>> {
+        let mut path_sums = HashMap::<NodeIndex, SparsePoly<F>>::new();
+        path_sums.insert(self.start_node, SparsePoly::from([(vec![], F::one())]));
+        for node in self.sorted_nodes() {
+            let from_sum = path_sums.get(&node).cloned().unwrap_or_default();
+            for edge in self.graph.edges(node) {
+                let to_sum = path_sums.entry(edge.target()).or_default();
+                mul_add_affine_form(to_sum, &from_sum, edge.weight());
+            }
+        }
+        self.node_order()
+            .iter()
+            .map(|node| from_sparse_poly(path_sums.remove(node).unwrap_or_default()))
+            .collect()
+    }
+
+    /// Returns the `L_x` matrix, i.e., `A_x - I` without the column of the start node, where `A_x` is the adjacency matrix in the order of [`Abp::node_order`].
+    pub fn lx_matrix(&self) -> DMatrix<AffineForm<F>> {
+        let node_order = self.node_order();
+        DMatrix::from_fn(node_order.len(), node_order.len() - 1, |i, j| {
+            let mut entry = match self.graph.find_edge(node_order[i], node_order[j + 1]) {
+                Some(edge) => self.graph[edge].clone(),
+                None => AffineForm::zero(),
+            };
+            if i == j + 1 {
+                entry.constant -= F::one();
+            }
+            entry
+        })
+    }
+
+    /// Computes the cofactors of `L_x` for every deleted row, placing the rows of the end nodes first, as expected by [`PhfeFunc`](super::PhfeFunc).
+    /// Since the ABP is a DAG, the cofactor of the row of a node is exactly the polynomial computed at that node, so no symbolic determinant is needed.
+    pub fn dfx_coeffs(&self) -> Vec<Polynomial<F>> {
+        let mut path_polys = self.path_polys();
+        let num_non_end_nodes = path_polys.len() - self.num_outputs();
+        let mut end_polys = path_polys.split_off(num_non_end_nodes);
+        end_polys.append(&mut path_polys);
+        end_polys
+    }
+
+    /// Splits `lx_bar`, the transpose of `L_x` without the rows of the end nodes, into its constant part `l0` and its linear part `l1` as expected by [`PhfeFunc`](super::PhfeFunc).
+    pub fn to_l0_and_l1(&self) -> (DMatrix<F>, DMatrix<F>) {
+        let lx = self.lx_matrix();
+        let num_rows = lx.ncols();
+        let num_cols = lx.nrows() - self.num_outputs();
+        let mut l0 = DMatrix::<F>::zeros(num_rows, num_cols);
+        let mut l1 = DMatrix::<F>::zeros(num_rows, num_cols * self.num_public_vars);
+        for i in 0..num_rows {
+            for j in 0..num_cols {
+                let entry = &lx[(j, i)];
+                l0[(i, j)] = entry.constant;
+                for (k, coeff) in entry.coeffs.iter() {
+                    l1[(i, *k as usize * num_cols + j)] = *coeff;
+                }
+            }
+        }
+        (l0, l1)
+    }
+
+    fn sorted_nodes(&self) -> Vec<NodeIndex> {
+        toposort(&self.graph, None).expect("The ABP must be acyclic.")
+    }
+}
+
+fn to_sparse_poly<F: PrimeField>(
+    poly: &Polynomial<F>,
+    num_public_vars: usize,
+) -> Result<SparsePoly<F>, Error> {
+    let mut sparse = SparsePoly::new();
+    for monomial in poly.monomials.iter() {
+        let mut indices = Vec::with_capacity(monomial.variables.len());
+        for variable in monomial.variables.iter() {
+            if variable.variable_type != VariableType::Public
+                || variable.index as usize >= num_public_vars
+            {
+                return Err(Error::InvalidPublicVariable(*variable, num_public_vars));
+            }
+            indices.push(variable.index);
+        }
+        indices.sort();
+        *sparse.entry(indices).or_insert(F::zero()) += monomial.coefficient;
+    }
+    sparse.retain(|_, coeff| !coeff.is_zero());
+    Ok(sparse)
+}
+
+fn from_sparse_poly<F: PrimeField>(sparse: SparsePoly<F>) -> Polynomial<F> {
+    let monomials = sparse
+        .into_iter()
+        .filter(|(_, coeff)| !coeff.is_zero())
+        .map(|(indices, coeff)| {
+            let variables = indices
+                .into_iter()
+                .map(|index| Variable {
+                    index,
+                    variable_type: VariableType::Public,
+                })
+                .collect();
+            Monomial::new(variables, coeff)
+        })
+        .collect();
+    Polynomial::new(monomials)
+}
+
+/// Adds `poly * form` to `acc`.
+fn mul_add_affine_form<F: PrimeField>(
+    acc: &mut SparsePoly<F>,
+    poly: &SparsePoly<F>,
+    form: &AffineForm<F>,
+) {
+    for (indices, coeff) in poly.iter() {
+        if !form.constant.is_zero() {
+            *acc.entry(indices.clone()).or_insert(F::zero()) += *coeff * form.constant;
+        }
+        for (index, linear_coeff) in form.coeffs.iter() {
+            let mut new_indices = indices.clone();
+            let pos = new_indices.partition_point(|i| i <= index);
+            new_indices.insert(pos, *index);
+            *acc.entry(new_indices).or_insert(F::zero()) += *coeff * linear_coeff;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_bn254::Fr;
+    use ark_ff::UniformRand;
+    use nalgebra::DVector;
+    use num_traits::Zero;
+    use std::str::FromStr;
+
+    fn random_assignment(num_public_vars: usize) -> (DVector<Fr>, HashMap<Variable, Fr>) {
+        let mut rng = rand::thread_rng();
+        let x = DVector::from_fn(num_public_vars, |_, _| Fr::rand(&mut rng));
+        let mut x_assignment = HashMap::<Variable, Fr>::new();
+        for (idx, val) in x.iter().enumerate() {
+            x_assignment.insert(
+                Variable {
+                    index: idx as u32,
+                    variable_type: VariableType::Public,
+                },
+                *val,
+            );
+        }
+        (x, x_assignment)
+    }
+
+    fn test_polys() -> Vec<Polynomial<Fr>> {
+        vec![
+            "x1 + x2 + 1",
+            "x0*x1 + x1*x2",
+            "x0*x2 + x1 + x2 + 1",
+            "3*x0*x1*x2 + 2*x1*x2 + 5",
+        ]
+        .into_iter()
+        .map(|s| Polynomial::from_str(s).unwrap())
+        .collect()
+    }
+
+    #[test]
+    fn test_eval() {
+        let polys = test_polys();
+        let abp = Abp::from_polynomials(3, &polys).unwrap();
+        let (_, x_assignment) = random_assignment(3);
+        let expected = polys
+            .iter()
+            .map(|poly| poly.eval(&x_assignment))
+            .collect_vec();
+        assert_eq!(abp.eval(&x_assignment), expected);
+        let dfx_coeffs = abp.dfx_coeffs();
+        for (idx, expected) in expected.iter().enumerate() {
+            assert_eq!(dfx_coeffs[idx].eval(&x_assignment), *expected);
+        }
+    }
+
+    #[test]
+    fn test_size() {
+        let polys = test_polys();
+        let abp = Abp::from_polynomials(3, &polys).unwrap();
+        // One start node, four end nodes, and the suffix nodes `x1`, `x2` of the second polynomial and `x2` of the third and `x1*x2`, `x2` of the fourth.
+        assert_eq!(abp.num_nodes(), 1 + 4 + 5);
+        let lx = abp.lx_matrix();
+        assert_eq!((lx.nrows(), lx.ncols()), (10, 9));
+        let (l0, l1) = abp.to_l0_and_l1();
+        assert_eq!((l0.nrows(), l0.ncols()), (9, 6));
+        assert_eq!((l1.nrows(), l1.ncols()), (9, 18));
+    }
+
+    #[test]
+    fn test_lx_matrix() {
+        let abp = Abp::from_polynomials(3, &test_polys()).unwrap();
+        let (x, x_assignment) = random_assignment(3);
+        let lx = abp.lx_matrix().map(|entry| entry.eval(&x_assignment));
+        // The path polynomials are in the left kernel of `L_x`.
+        let path_sums = DVector::from_iterator(
+            lx.nrows(),
+            abp.path_polys().iter().map(|poly| poly.eval(&x_assignment)),
+        );
+        assert_eq!(path_sums[0], Fr::from(1u64));
+        assert!((path_sums.transpose() * &lx)
+            .iter()
+            .all(|val| val.is_zero()));
+        // `l1 * (x ⊗ I) + l0` is the transpose of `L_x` without the rows of the end nodes.
+        let (l0, l1) = abp.to_l0_and_l1();
+        let identity = DMatrix::<Fr>::identity(l0.ncols(), l0.ncols());
+        let lx_bar = &l1 * x.kronecker(&identity) + &l0;
+        assert_eq!(lx_bar, lx.rows(0, l0.ncols()).transpose());
+    }
+
+    #[test]
+    fn test_manual_abp() {
+        // (x0 + 2) * (3 * x1 + 1) + x0
+        let mut abp = Abp::<Fr>::new(2, 1);
+        let middle = abp.add_node();
+        let start = abp.start_node();
+        let end = abp.end_nodes()[0];
+        abp.add_edge(
+            start,
+            middle,
+            AffineForm::variable(0, Fr::from(1u64)) + AffineForm::constant(Fr::from(2u64)),
+        )
+        .unwrap();
+        abp.add_edge(
+            middle,
+            end,
+            AffineForm::new(Fr::from(1u64), BTreeMap::from([(1, Fr::from(3u64))])),
+        )
+        .unwrap();
+        abp.add_edge(start, end, AffineForm::variable(0, Fr::from(1u64)))
+            .unwrap();
+        assert!(matches!(
+            abp.add_edge(middle, end, AffineForm::variable(2, Fr::from(1u64))),
+            Err(Error::InvalidPublicVariable(_, 2))
+        ));
+        let one = || AffineForm::constant(Fr::from(1u64));
+        let other = abp.add_node();
+        assert!(matches!(
+            abp.add_edge(middle, start, one()),
+            Err(Error::InvalidAbpEdge(..))
+        ));
+        assert!(matches!(
+            abp.add_edge(end, other, one()),
+            Err(Error::InvalidAbpEdge(..))
+        ));
+        assert!(matches!(
+            abp.add_edge(middle, middle, one()),
+            Err(Error::InvalidAbpEdge(..))
+        ));
+        abp.add_edge(middle, other, one()).unwrap();
+        assert!(matches!(
+            abp.add_edge(other, middle, one()),
+            Err(Error::InvalidAbpEdge(..))
+        ));
+        assert!(matches!(
+            abp.add_edge(other, NodeIndex::new(100), one()),
+            Err(Error::InvalidAbpEdge(..))
+        ));
+        assert_eq!(abp.num_edges(), 4);
+        let (_, x_assignment) = random_assignment(2);
+        let x0 = x_assignment[&Variable {
+            index: 0,
+            variable_type: VariableType::Public,
+        }];
+        let x1 = x_assignment[&Variable {
+            index: 1,
+            variable_type: VariableType::Public,
+        }];
+        let expected = (x0 + Fr::from(2u64)) * (Fr::from(3u64) * x1 + Fr::from(1u64)) + x0;
+        assert_eq!(abp.eval(&x_assignment), vec![expected]);
+        assert_eq!(abp.dfx_coeffs()[0].eval(&x_assignment), expected);
+    }
+}
@@ -1,5 +1,9 @@
+use super::partial_garbling::partial_garbling_polys;
 use super::*;
-use crate::{poly::Polynomial, Error};
+use crate::{
+    poly::{CompiledPolynomials, Polynomial},
+    Error,
+};
 use ark_ec::{
     pairing::{Pairing, PairingOutput},
     Group,
@@ -48,9 +52,38 @@ impl<F: PrimeField> PhfeFunc<F> {
         }
     }
 
-    pub fn from_str(s: &str) -> Result<Self, Error> {
-        let json: PhfeFuncJson = serde_json::from_str(s)?;
-        Self::from_json(json)
+    pub fn from_polys(
+        num_public_vars: usize,
+        num_private_vars1: usize,
+        num_private_vars2: usize,
+        polys: Vec<Polynomial<F>>,
+    ) -> Result<Self, Error> {
+        partial_garbling_polys(num_public_vars, num_private_vars1, num_private_vars2, polys)
+    }
+
+    /// Builds the function computing `poly`, a polynomial over both public and private variables, from its coefficient polynomials given by [`Polynomial::decompose_bilinear`].
+    pub fn from_mixed_poly(
+        num_public_vars: usize,
+        num_private_vars1: usize,
+        num_private_vars2: usize,
+        poly: &Polynomial<F>,
+    ) -> Result<Self, Error> {
+        let polys =
+            poly.decompose_bilinear(num_public_vars, num_private_vars1, num_private_vars2)?;
+        Self::from_polys(num_public_vars, num_private_vars1, num_private_vars2, polys)
+    }
+
+    /// Evaluates the function in the clear, i.e., returns `sum_{i,j} z1[i] * z2[j] * polys[i * num_private_vars2 + j](x)`, which [`super::Phfe::dec`] outputs in the exponent.
+    pub fn eval(&self, x: &DVector<F>, z1: &DVector<F>, z2: &DVector<F>) -> Result<F, Error> {
+        debug_assert_eq!(z1.len(), self.num_private_vars1);
+        debug_assert_eq!(z2.len(), self.num_private_vars2);
+        let fx = CompiledPolynomials::new(&self.polys).eval(x.as_slice(), &[])?;
+        Ok(z1
+            .kronecker(z2)
+            .iter()
+            .zip(fx.iter())
+            .map(|(z, fx)| *z * fx)
+            .sum())
     }
 
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
@@ -88,15 +121,25 @@ impl<F: PrimeField> PhfeFunc<F> {
         }
         let l1 = DMatrix::from_row_slice(l1_rows, l1_cols, &l1_vec);
 
-        Ok(Self {
-            num_public_vars: json.num_public_vars,
-            num_private_vars1: json.num_private_vars1,
-            num_private_vars2: json.num_private_vars2,
+        Ok(Self::new(
+            json.num_public_vars,
+            json.num_private_vars1,
+            json.num_private_vars2,
             polys,
             dfx_coeffs,
             l0,
             l1,
-        })
+        ))
+    }
+}
+
+impl<F: PrimeField> FromStr for PhfeFunc<F> {
+    type Err = Error;
+
+    /// Parses the JSON of [`PhfeFuncJson`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let json: PhfeFuncJson = serde_json::from_str(s)?;
+        Self::from_json(json)
     }
 }
 
@@ -1,6 +1,6 @@
 use super::*;
-use crate::poly::{Monomial, Polynomial};
-use crate::poly::{Variable, VariableType};
+use crate::poly::{CompiledPolynomials, Monomial, Polynomial};
+use crate::Error;
 use ark_ec::pairing::PairingOutput;
 use ark_ec::{pairing::Pairing, Group};
 use ark_ff::PrimeField;
@@ -12,7 +12,6 @@ use nalgebra::U1;
 use nalgebra::{DMatrix, DVector};
 use num_traits::{One, Zero};
 use rand::Rng;
-use std::collections::HashMap;
 use std::marker::PhantomData;
 use utils::*;
 // use utils::mul_scalar_vec_and_g1_matrix;
@@ -169,30 +168,21 @@ impl<C: Pairing> LinearPhfe<C> {
         }
     }
 
+    /// Decrypts `ct` with `fsk`, returning [`Error::MissingVariableValues`] if `ct` has fewer public values than the variables of `f.dfx_coeffs`.
     pub fn dec(
         &self,
         ct: &LinearPhfeCt<C>,
         f: &PhfeFunc<C::ScalarField>,
         fsk: &LinearPhfeFsk<C>,
-    ) -> PairingOutput<C> {
+    ) -> Result<PairingOutput<C>, Error> {
+        let dfx = CompiledPolynomials::new(&f.dfx_coeffs).eval(ct.x.as_slice(), &[])?;
         let p1 = &ct.z_vec.transpose() * &fsk.m_matrix + ct.sa_vec.transpose() * (-&fsk.tm_matrix);
         let x_tensored =
             vec_tensor_product_with_identity(&ct.x.map(|s| PhfeElement::Scalar(s)), f.l0.ncols());
         let p2 = &ct.sa_vec.transpose() * (&fsk.l1_matrix * x_tensored + &fsk.l0_matrix)
             + (-&ct.x_vec.transpose()) * &fsk.r_matrix;
-        let mut x_assignment = HashMap::<Variable, C::ScalarField>::new();
-        for (idx, val) in ct.x.into_iter().enumerate() {
-            x_assignment.insert(
-                Variable {
-                    index: idx as u32,
-                    variable_type: VariableType::Public,
-                },
-                *val,
-            );
-        }
-        let dfx_vec = DVector::<PhfeElement<C>>::from_fn(f.dfx_coeffs.len(), |i, _| {
-            PhfeElement::Scalar(f.dfx_coeffs[i].eval(&x_assignment))
-        });
+        let dfx_vec =
+            DVector::<PhfeElement<C>>::from_fn(dfx.len(), |i, _| PhfeElement::Scalar(dfx[i]));
         debug_assert_eq!(p1.nrows(), p2.nrows());
         let p12: DMatrix<PhfeElement<C>> =
             DMatrix::from_fn(p1.nrows(), p1.ncols() + p2.ncols(), |i, j| {
@@ -204,7 +194,7 @@ impl<C: Pairing> LinearPhfe<C> {
             });
         let result_gt: Matrix<PhfeElement<C>, Dyn, U1, _> = p12 * dfx_vec;
         match result_gt[(0, 0)] {
-            PhfeElement::Gt(gt) => gt,
+            PhfeElement::Gt(gt) => Ok(gt),
             _ => panic!("Pairing output should be on Gt"),
         }
     }
@@ -0,0 +1,159 @@
+use super::abp::Abp;
+use super::*;
+use crate::poly::Polynomial;
+use crate::Error;
+use ark_ff::PrimeField;
+
+/// Compiles `polys` into the partial garbling consumed by [`Phfe`], i.e., the [`PhfeFunc`] whose `dfx_coeffs`, `l0` and `l1` are derived from the [`Abp`] computing all of `polys`.
+/// It is the Rust counterpart of `partial_garbling_polys` in `script/src/phfe.py`, but every value is computed exactly over `F`.
+pub fn partial_garbling_polys<F: PrimeField>(
+    num_public_vars: usize,
+    num_private_vars1: usize,
+    num_private_vars2: usize,
+    polys: Vec<Polynomial<F>>,
+) -> Result<PhfeFunc<F>, Error> {
+    if num_private_vars1 * num_private_vars2 != polys.len() {
+        return Err(Error::InvalidNumPolys(
+            polys.len(),
+            num_private_vars1 * num_private_vars2,
+        ));
+    }
+    let abp = Abp::from_polynomials(num_public_vars, &polys)?;
+    let (l0, l1) = abp.to_l0_and_l1();
+    let dfx_coeffs = abp.dfx_coeffs();
+    Ok(PhfeFunc::new(
+        num_public_vars,
+        num_private_vars1,
+        num_private_vars2,
+        polys,
+        dfx_coeffs,
+        l0,
+        l1,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::poly::{Variable, VariableType};
+    use ark_bn254::Fr;
+    use ark_ff::UniformRand;
+    use itertools::Itertools;
+    use nalgebra::{DMatrix, DVector};
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    fn check_partial_garbling(func: &PhfeFunc<Fr>) {
+        let mut rng = rand::thread_rng();
+        let x = DVector::from_fn(func.num_public_vars, |_, _| Fr::rand(&mut rng));
+        let z1 = DVector::from_fn(func.num_private_vars1, |_, _| Fr::rand(&mut rng));
+        let z2 = DVector::from_fn(func.num_private_vars2, |_, _| Fr::rand(&mut rng));
+        let mut x_assignment = HashMap::<Variable, Fr>::new();
+        for (idx, val) in x.iter().enumerate() {
+            x_assignment.insert(
+                Variable {
+                    index: idx as u32,
+                    variable_type: VariableType::Public,
+                },
+                *val,
+            );
+        }
+        let num_polys = func.polys.len();
+        let lx_bar = &func.l1
+            * (&x.kronecker(&DMatrix::<Fr>::identity(func.l0.ncols(), func.l0.ncols())))
+            + &func.l0;
+        let t = DMatrix::<Fr>::from_fn(1, func.l0.nrows(), |_, _| Fr::rand(&mut rng));
+        let t_bar = t.columns(t.ncols() - num_polys, num_polys);
+        let zs = z1.kronecker(&z2).transpose();
+        let dfx = DVector::from_iterator(
+            func.dfx_coeffs.len(),
+            func.dfx_coeffs.iter().map(|f| f.eval(&x_assignment)),
+        );
+        let out = (zs.clone() - t_bar) * dfx.rows(0, num_polys)
+            + &t * lx_bar * dfx.rows(num_polys, dfx.len() - num_polys);
+        let fx =
+            DVector::from_iterator(num_polys, func.polys.iter().map(|f| f.eval(&x_assignment)));
+        assert_eq!(out[(0, 0)], (zs * fx)[(0, 0)]);
+    }
+
+    #[test]
+    fn test_partial_garbling1() {
+        let polys = vec![
+            "x1 + x2 + 1",
+            "x0*x1 + x1*x2",
+            "x0*x2 + x1 + x2 + 1",
+            "x0*x1 + x1*x2",
+            "x0*x2 + x1 + x0",
+            "x0*x1 + x1*x2 + 1",
+        ]
+        .into_iter()
+        .map(|s| Polynomial::<Fr>::from_str(s).unwrap())
+        .collect_vec();
+        let func = partial_garbling_polys(3, 2, 3, polys).unwrap();
+        check_partial_garbling(&func);
+    }
+
+    #[test]
+    fn test_partial_garbling2() {
+        let polys = vec![
+            "x0*x1*x2*x3*x4 + x5 + x6",
+            "x1*x2*x3*x4*x5 + x6 + x7",
+            "x0 + x1 + x2*x3*x4*x5*x6",
+            "x3*x4*x5*x6*x7 + x8 + x9",
+            "x0 + x2 + x4*x5*x6*x7*x8",
+            "x1 + x3 + x5*x6*x7*x8*x9",
+            "x10*x6*x7*x8*x9 + x2 + x4",
+            "x10*x11*x7*x8*x9 + x3 + x5",
+            "x0 + x10*x11*x12*x8*x9 + x6",
+            "x1 + x10*x11*x12*x13*x9 + x7",
+        ]
+        .into_iter()
+        .map(|s| Polynomial::<Fr>::from_str(s).unwrap())
+        .collect_vec();
+        let func = partial_garbling_polys(14, 2, 5, polys).unwrap();
+        check_partial_garbling(&func);
+    }
+
+    #[test]
+    fn test_dfx_coeffs_of_end_nodes() {
+        let polys = vec!["3*x0*x1 + 2*x1 + 5", "x0*x1*x1 + 7*x1*x0 + x0"]
+            .into_iter()
+            .map(|s| Polynomial::<Fr>::from_str(s).unwrap())
+            .collect_vec();
+        let func = partial_garbling_polys(2, 1, 2, polys.clone()).unwrap();
+        check_partial_garbling(&func);
+        let mut rng = rand::thread_rng();
+        let mut x_assignment = HashMap::<Variable, Fr>::new();
+        for idx in 0..2 {
+            x_assignment.insert(
+                Variable {
+                    index: idx,
+                    variable_type: VariableType::Public,
+                },
+                Fr::rand(&mut rng),
+            );
+        }
+        for (poly, dfx_coeff) in polys.iter().zip(func.dfx_coeffs.iter()) {
+            assert_eq!(poly.eval(&x_assignment), dfx_coeff.eval(&x_assignment));
+        }
+    }
+
+    #[test]
+    fn test_invalid_inputs() {
+        let polys = vec![Polynomial::<Fr>::from_str("x0 + 1").unwrap()];
+        assert!(matches!(
+            partial_garbling_polys(1, 1, 2, polys),
+            Err(Error::InvalidNumPolys(1, 2))
+        ));
+        let polys = vec![Polynomial::<Fr>::from_str("x0 + x3").unwrap()];
+        assert!(matches!(
+            partial_garbling_polys(2, 1, 1, polys),
+            Err(Error::InvalidPublicVariable(_, 2))
+        ));
+        let polys = vec![Polynomial::<Fr>::from_str("x0*z0").unwrap()];
+        assert!(matches!(
+            partial_garbling_polys(2, 1, 1, polys),
+            Err(Error::InvalidPublicVariable(_, 2))
+        ));
+    }
+}
@@ -1,10 +1,20 @@
 use crate::Error;
 use ark_ff::{Field, PrimeField};
-use regex::Regex;
+use itertools::Itertools;
+use num_traits::{One, Zero};
+use parser::PolynomialParser;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 use std::str::FromStr;
 
+mod bilinear;
+mod compiled;
+mod parser;
+pub use compiled::CompiledPolynomials;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum VariableType {
     Private,
@@ -17,6 +27,25 @@ pub struct Variable {
     pub variable_type: VariableType,
 }
 
+/// The public variables come first and the variables of the same type are ordered by their indices, e.g., `x0 < x1 < z0`.
+impl Ord for Variable {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let key = |variable: &Self| {
+            (
+                variable.variable_type == VariableType::Private,
+                variable.index,
+            )
+        };
+        key(self).cmp(&key(other))
+    }
+}
+
+impl PartialOrd for Variable {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Monomial<F: PrimeField> {
     pub variables: Vec<Variable>,
@@ -43,83 +72,95 @@ impl<F: PrimeField> Monomial<F> {
         result *= self.coefficient;
         result
     }
+
+    /// Sorts the variables so that the monomials of the same variables compare equal.
+    pub fn normalize(&mut self) {
+        self.variables.sort();
+    }
+}
+
+/// Writes `x{i}` for a public variable and `z{i}` for a private one.
+impl fmt::Display for Variable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.variable_type {
+            VariableType::Public => write!(f, "x{}", self.index),
+            VariableType::Private => write!(f, "z{}", self.index),
+        }
+    }
+}
+
+/// Writes the monomial like `-2*x0**2*z1`, where a coefficient greater than `(p-1)/2` is written as the negative integer of the same field element.
+impl<F: PrimeField> fmt::Display for Monomial<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (is_negative, abs) = signed_coefficient(self.coefficient);
+        if is_negative {
+            write!(f, "-")?;
+        }
+        fmt_unsigned_monomial(f, &self.variables, abs)
+    }
+}
+
+/// Returns whether `coefficient` is negative as a signed integer in `(-p/2, p/2)` and its absolute value.
+fn signed_coefficient<F: PrimeField>(coefficient: F) -> (bool, F) {
+    if coefficient.into_bigint() > F::MODULUS_MINUS_ONE_DIV_TWO {
+        (true, -coefficient)
+    } else {
+        (false, coefficient)
+    }
+}
+
+/// Writes `abs` and `variables` joined by `*`, omitting `abs` if it is one and writing consecutive copies of a variable as a power.
+fn fmt_unsigned_monomial<F: PrimeField>(
+    f: &mut fmt::Formatter<'_>,
+    variables: &[Variable],
+    abs: F,
+) -> fmt::Result {
+    let mut factors = vec![];
+    if variables.is_empty() || !abs.is_one() {
+        factors.push(abs.into_bigint().to_string());
+    }
+    for (count, variable) in variables.iter().dedup_with_count() {
+        if count == 1 {
+            factors.push(variable.to_string());
+        } else {
+            factors.push(format!("{}**{}", variable, count));
+        }
+    }
+    write!(f, "{}", factors.join("*"))
+}
+
+impl<F: PrimeField> Mul for &Monomial<F> {
+    type Output = Monomial<F>;
+
+    fn mul(self, rhs: Self) -> Monomial<F> {
+        let mut variables = self.variables.clone();
+        variables.extend_from_slice(&rhs.variables);
+        let mut monomial = Monomial::new(variables, self.coefficient * rhs.coefficient);
+        monomial.normalize();
+        monomial
+    }
 }
 
 impl<F: PrimeField> FromStr for Monomial<F> {
     type Err = Error;
 
+    /// Parses `s` with the same grammar as [`Polynomial::from_str`] and requires the result to be a single monomial, e.g., `-2*x0*z1` or `x0^2`.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<&str> = s.trim().split('*').collect();
-        let re_minus = Regex::new(r"-$").unwrap();
-        let re_const = Regex::new(r"\d+$").unwrap();
-        let re_public_var = Regex::new(r"x\d+$").unwrap();
-        let re_private_var = Regex::new(r"z\d+$").unwrap();
-
-        let mut coeff = None;
-        let mut minus_coeff = F::one();
-        let mut variables = Vec::new();
-        for part in parts {
-            if re_minus.is_match(part) {
-                minus_coeff = F::zero() - F::one();
-            }
-            match part {
-                _ if re_public_var.is_match(part) => {
-                    let index = u32::from_str(&part[1..]).map_err(|_| {
-                        Error::InvalidMonomialString(
-                            s.to_string(),
-                            format!("failed to parse {} as a public variable index", part),
-                        )
-                    })?;
-                    variables.push(Variable {
-                        index,
-                        variable_type: VariableType::Public,
-                    });
-                }
-                _ if re_private_var.is_match(part) => {
-                    let index = u32::from_str(&part[1..]).map_err(|_| {
-                        Error::InvalidMonomialString(
-                            s.to_string(),
-                            format!("failed to parse {} as a private variable index", part),
-                        )
-                    })?;
-                    variables.push(Variable {
-                        index,
-                        variable_type: VariableType::Private,
-                    });
-                }
-                _ if re_const.is_match(part) => {
-                    if coeff.is_some() {
-                        return Err(Error::InvalidMonomialString(
-                            s.to_string(),
-                            format!(
-                                "coefficient {} already appeared, but {} appeared again",
-                                coeff.unwrap(),
-                                part
-                            ),
-                        ));
-                    }
-                    coeff = Some(F::from_str(part).map_err(|_| {
-                        Error::InvalidMonomialString(
-                            s.to_string(),
-                            format!("failed to parse {} as a field element", part),
-                        )
-                    })?);
-                }
-                _ => {
-                    return Err(Error::InvalidMonomialString(
-                        s.to_string(),
-                        format!(
-                        "failed to parse {} as a constant, public variable, or private variable",
-                        part
-                    ),
-                    ));
-                }
+        let poly = PolynomialParser::parse::<F>(s).map_err(|err| match err {
+            Error::InvalidPolynomialString(_, reason) => {
+                Error::InvalidMonomialString(s.to_string(), reason)
             }
+            err => err,
+        })?;
+        let mut monomials = poly.monomials.into_iter();
+        match (monomials.next(), monomials.next()) {
+            (None, _) => Ok(Self::new(vec![], F::zero())),
+            (Some(monomial), None) => Ok(monomial),
+            (Some(_), Some(_)) => Err(Error::InvalidMonomialString(
+                s.to_string(),
+                "more than one monomial found".to_string(),
+            )),
         }
-        Ok(Self {
-            variables,
-            coefficient: minus_coeff * coeff.unwrap_or(F::one()),
-        })
     }
 }
 
@@ -147,23 +188,350 @@ impl<F: PrimeField> Polynomial<F> {
             .map(|monomial| monomial.eval(assignment))
             .sum()
     }
+
+    /// Returns the polynomial of the single monomial `coefficient`.
+    pub fn constant(coefficient: F) -> Self {
+        Self::new(vec![Monomial::new(vec![], coefficient)]).normalized()
+    }
+
+    /// Returns the polynomial of the single monomial `variable`.
+    pub fn variable(variable: Variable) -> Self {
+        Self::new(vec![Monomial::new(vec![variable], F::one())])
+    }
+
+    /// Converts the polynomial into its canonical form, where the variables in each monomial are sorted, the monomials of the same variables are merged, the monomials with zero coefficients are dropped, and the monomials are sorted by their degrees and then their variables.
+    /// Two polynomials in the canonical form are equal if and only if they are equal as polynomials.
+    pub fn normalize(&mut self) {
+        let mut merged = BTreeMap::<(usize, Vec<Variable>), F>::new();
+        for mut monomial in self.monomials.drain(..) {
+            monomial.normalize();
+            *merged
+                .entry((monomial.degree(), monomial.variables))
+                .or_insert(F::zero()) += monomial.coefficient;
+        }
+        self.monomials = merged
+            .into_iter()
+            .filter(|(_, coefficient)| !coefficient.is_zero())
+            .map(|((_, variables), coefficient)| Monomial::new(variables, coefficient))
+            .collect();
+    }
+
+    /// Returns the canonical form of the polynomial described in [`Polynomial::normalize`].
+    pub fn normalized(mut self) -> Self {
+        self.normalize();
+        self
+    }
+
+    /// Returns the polynomial multiplied by `scalar`.
+    pub fn scale(&self, scalar: F) -> Self {
+        Self::new(
+            self.monomials
+                .iter()
+                .map(|monomial| {
+                    Monomial::new(monomial.variables.clone(), monomial.coefficient * scalar)
+                })
+                .collect(),
+        )
+        .normalized()
+    }
+}
+
+/// Writes the monomials joined by ` + ` or ` - `, e.g., `x0*x1 - 2*z0 + 1`, and `0` for the polynomial without monomials.
+/// [`Polynomial::from_str`] parses the string back into the normalized polynomial.
+impl<F: PrimeField> fmt::Display for Polynomial<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.monomials.is_empty() {
+            return write!(f, "0");
+        }
+        for (idx, monomial) in self.monomials.iter().enumerate() {
+            let (is_negative, abs) = signed_coefficient(monomial.coefficient);
+            match (idx, is_negative) {
+                (0, false) => {}
+                (0, true) => write!(f, "-")?,
+                (_, false) => write!(f, " + ")?,
+                (_, true) => write!(f, " - ")?,
+            }
+            fmt_unsigned_monomial(f, &monomial.variables, abs)?;
+        }
+        Ok(())
+    }
+}
+
+impl<F: PrimeField> Zero for Polynomial<F> {
+    fn zero() -> Self {
+        Self::new(vec![])
+    }
+
+    fn is_zero(&self) -> bool {
+        self.monomials
+            .iter()
+            .all(|monomial| monomial.coefficient.is_zero())
+    }
+}
+
+impl<F: PrimeField> One for Polynomial<F> {
+    fn one() -> Self {
+        Self::constant(F::one())
+    }
+}
+
+impl<F: PrimeField> Add for &Polynomial<F> {
+    type Output = Polynomial<F>;
+
+    fn add(self, rhs: Self) -> Polynomial<F> {
+        let mut monomials = self.monomials.clone();
+        monomials.extend_from_slice(&rhs.monomials);
+        Polynomial::new(monomials).normalized()
+    }
+}
+
+impl<F: PrimeField> Add for Polynomial<F> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        &self + &rhs
+    }
+}
+
+impl<F: PrimeField> AddAssign for Polynomial<F> {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = &*self + &rhs;
+    }
+}
+
+impl<F: PrimeField> Neg for &Polynomial<F> {
+    type Output = Polynomial<F>;
+
+    fn neg(self) -> Polynomial<F> {
+        self.scale(-F::one())
+    }
+}
+
+impl<F: PrimeField> Neg for Polynomial<F> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        -&self
+    }
+}
+
+impl<F: PrimeField> Sub for &Polynomial<F> {
+    type Output = Polynomial<F>;
+
+    fn sub(self, rhs: Self) -> Polynomial<F> {
+        self + &(-rhs)
+    }
+}
+
+impl<F: PrimeField> Sub for Polynomial<F> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        &self - &rhs
+    }
+}
+
+impl<F: PrimeField> SubAssign for Polynomial<F> {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = &*self - &rhs;
+    }
+}
+
+impl<F: PrimeField> Mul for &Polynomial<F> {
+    type Output = Polynomial<F>;
+
+    fn mul(self, rhs: Self) -> Polynomial<F> {
+        let monomials = self
+            .monomials
+            .iter()
+            .flat_map(|a| rhs.monomials.iter().map(move |b| a * b))
+            .collect();
+        Polynomial::new(monomials).normalized()
+    }
+}
+
+impl<F: PrimeField> Mul for Polynomial<F> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        &self * &rhs
+    }
+}
+
+impl<F: PrimeField> MulAssign for Polynomial<F> {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = &*self * &rhs;
+    }
+}
+
+impl<F: PrimeField> Mul<F> for Polynomial<F> {
+    type Output = Self;
+
+    fn mul(self, rhs: F) -> Self {
+        self.scale(rhs)
+    }
+}
+
+impl<F: PrimeField> MulAssign<F> for Polynomial<F> {
+    fn mul_assign(&mut self, rhs: F) {
+        *self = self.scale(rhs);
+    }
 }
 
 impl<F: PrimeField> FromStr for Polynomial<F> {
     type Err = Error;
 
+    /// Parses an expression of integers and the variables `x{i}` and `z{i}` with `+`, `-`, `*`, `**` or `^` by a non-negative integer, and parentheses, e.g., `(x0 + 1)*x2 - 2*x0**2`.
+    /// The result is expanded into the canonical form described in [`Polynomial::normalize`].
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<&str> = s.split('+').collect();
-        if parts.is_empty() {
-            return Err(Error::InvalidPolynomialString(
-                s.to_string(),
-                "no monomials found".to_string(),
-            ));
-        }
-        let mut monomials = Vec::new();
-        for part in parts {
-            monomials.push(Monomial::from_str(part)?);
+        PolynomialParser::parse(s)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_bn254::Fr;
+    use ark_ff::UniformRand;
+    use rand::Rng;
+
+    fn x(index: u32) -> Polynomial<Fr> {
+        Polynomial::variable(Variable {
+            index,
+            variable_type: VariableType::Public,
+        })
+    }
+
+    fn z(index: u32) -> Polynomial<Fr> {
+        Polynomial::variable(Variable {
+            index,
+            variable_type: VariableType::Private,
+        })
+    }
+
+    fn c(value: u64) -> Polynomial<Fr> {
+        Polynomial::constant(Fr::from(value))
+    }
+
+    #[test]
+    fn test_normalize() {
+        let poly = Polynomial::<Fr>::from_str("x1*x0 + 2*z0*x1 + x0*x1 + 3 + 0*x2 + 2").unwrap();
+        let normalized = poly.normalized();
+        assert_eq!(normalized.monomials.len(), 3);
+        assert_eq!(
+            normalized.monomials[0],
+            Monomial::new(vec![], Fr::from(5u64))
+        );
+        assert_eq!(normalized, (c(5) + c(2) * x(0) * x(1) + c(2) * x(1) * z(0)));
+        assert!((x(0) - x(0)).is_zero());
+        assert!((x(0) - x(0)).monomials.is_empty());
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        // (x0 + 1) * (x0 - 1) = x0^2 - 1
+        let product = (x(0) + c(1)) * (x(0) - c(1));
+        assert_eq!(product, x(0) * x(0) - Polynomial::one());
+        assert_eq!(product.degree(), 2);
+        assert_eq!(-(-x(0)), x(0));
+        assert_eq!(x(1) * Fr::from(3u64), x(1) + x(1) + x(1));
+        assert_eq!(x(0) * z(1), z(1) * x(0));
+        assert_eq!(x(0) * Polynomial::zero(), Polynomial::zero());
+
+        let mut acc = Polynomial::<Fr>::zero();
+        acc += x(0);
+        acc *= x(1) + c(2);
+        acc -= x(0) * x(1);
+        acc *= Fr::from(2u64);
+        assert_eq!(acc, x(0) * c(4));
+
+        let assignment = HashMap::from([
+            (
+                Variable {
+                    index: 0,
+                    variable_type: VariableType::Public,
+                },
+                Fr::from(3u64),
+            ),
+            (
+                Variable {
+                    index: 1,
+                    variable_type: VariableType::Public,
+                },
+                Fr::from(5u64),
+            ),
+        ]);
+        let a = x(0) * x(1) + c(7);
+        let b = x(1) - x(0) * c(2);
+        assert_eq!(
+            (&a * &b).eval(&assignment),
+            a.eval(&assignment) * b.eval(&assignment)
+        );
+        assert_eq!(
+            (&a - &b).eval(&assignment),
+            a.eval(&assignment) - b.eval(&assignment)
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let poly = x(0) * x(1) - z(0) * Fr::from(2u64) + c(1);
+        assert_eq!(poly.to_string(), "1 - 2*z0 + x0*x1");
+        assert_eq!((x(3) * x(3) * z(1)).to_string(), "x3**2*z1");
+        assert_eq!((-x(0) - c(1)).to_string(), "-1 - x0");
+        assert_eq!(Polynomial::<Fr>::zero().to_string(), "0");
+        assert_eq!(
+            Monomial::new(vec![], -Fr::from(7u64)).to_string(),
+            "-7".to_string()
+        );
+    }
+
+    /// Returns a random polynomial which is not normalized, with small and large coefficients.
+    fn random_poly<R: Rng>(rng: &mut R) -> Polynomial<Fr> {
+        let monomials = (0..rng.gen_range(0..6))
+            .map(|_| {
+                let variables = (0..rng.gen_range(0..4))
+                    .map(|_| Variable {
+                        index: rng.gen_range(0..4),
+                        variable_type: if rng.gen_bool(0.5) {
+                            VariableType::Public
+                        } else {
+                            VariableType::Private
+                        },
+                    })
+                    .collect();
+                let coefficient = match rng.gen_range(0..3) {
+                    0 => Fr::from(rng.gen_range(0..4u64)),
+                    1 => -Fr::from(rng.gen_range(1..4u64)),
+                    _ => Fr::rand(rng),
+                };
+                Monomial::new(variables, coefficient)
+            })
+            .collect();
+        Polynomial::new(monomials)
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..200 {
+            let poly = random_poly(&mut rng);
+            let normalized = poly.clone().normalized();
+            assert_eq!(Polynomial::from_str(&poly.to_string()).unwrap(), normalized);
+            assert_eq!(
+                Polynomial::from_str(&normalized.to_string()).unwrap(),
+                normalized
+            );
+            for monomial in poly.monomials.iter() {
+                let mut expected = monomial.clone();
+                expected.normalize();
+                let parsed = Monomial::<Fr>::from_str(&monomial.to_string()).unwrap();
+                if expected.coefficient.is_zero() {
+                    assert!(parsed.coefficient.is_zero());
+                } else {
+                    assert_eq!(parsed, expected);
+                }
+            }
         }
-        Ok(Self { monomials })
     }
 }
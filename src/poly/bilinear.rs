@@ -0,0 +1,147 @@
+use super::*;
+
+/// The bilinear layout of the private variables expected by [`crate::phfe::PhfeFunc`]:
+/// `z{i}` for `i < num_private_vars1` is the `i`-th first private variable and `z{num_private_vars1 + j}` is the `j`-th second one,
+/// and the polynomial `sum_{i,j} z{i} * z{num_private_vars1 + j} * polys[i * num_private_vars2 + j]` has the coefficient polynomials `polys` in the public variables.
+impl<F: PrimeField> Polynomial<F> {
+    /// Returns the maximum number of the private variables in a monomial, counted with multiplicity.
+    pub fn private_degree(&self) -> usize {
+        self.monomials
+            .iter()
+            .map(|monomial| {
+                monomial
+                    .variables
+                    .iter()
+                    .filter(|variable| variable.variable_type == VariableType::Private)
+                    .count()
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Decomposes the polynomial over `x{0..num_public_vars}` and `z{0..num_private_vars1 + num_private_vars2}` into the `num_private_vars1 * num_private_vars2` coefficient polynomials in the bilinear layout.
+    /// Every monomial must consist of exactly one first private variable, exactly one second private variable and public variables.
+    pub fn decompose_bilinear(
+        &self,
+        num_public_vars: usize,
+        num_private_vars1: usize,
+        num_private_vars2: usize,
+    ) -> Result<Vec<Polynomial<F>>, Error> {
+        let num_private_vars = num_private_vars1 + num_private_vars2;
+        let mut polys = vec![Polynomial::zero(); num_private_vars1 * num_private_vars2];
+        for monomial in self.monomials.iter() {
+            let mut public_vars = vec![];
+            let mut private_vars1 = vec![];
+            let mut private_vars2 = vec![];
+            for variable in monomial.variables.iter() {
+                let index = variable.index as usize;
+                match variable.variable_type {
+                    VariableType::Public if index < num_public_vars => public_vars.push(*variable),
+                    VariableType::Public => {
+                        return Err(Error::InvalidPublicVariable(*variable, num_public_vars))
+                    }
+                    VariableType::Private if index < num_private_vars1 => private_vars1.push(index),
+                    VariableType::Private if index < num_private_vars => {
+                        private_vars2.push(index - num_private_vars1)
+                    }
+                    VariableType::Private => {
+                        return Err(Error::InvalidPrivateVariable(*variable, num_private_vars))
+                    }
+                }
+            }
+            match (private_vars1.as_slice(), private_vars2.as_slice()) {
+                ([i], [j]) => polys[i * num_private_vars2 + j]
+                    .monomials
+                    .push(Monomial::new(public_vars, monomial.coefficient)),
+                _ => return Err(Error::NonBilinearMonomial(monomial.to_string())),
+            }
+        }
+        Ok(polys.into_iter().map(Polynomial::normalized).collect())
+    }
+
+    /// Returns the polynomial whose coefficient polynomials in the bilinear layout are `polys`, the inverse of [`Polynomial::decompose_bilinear`].
+    pub fn compose_bilinear(
+        num_private_vars1: usize,
+        num_private_vars2: usize,
+        polys: &[Polynomial<F>],
+    ) -> Result<Self, Error> {
+        if num_private_vars1 * num_private_vars2 != polys.len() {
+            return Err(Error::InvalidNumPolys(
+                polys.len(),
+                num_private_vars1 * num_private_vars2,
+            ));
+        }
+        let private_var = |index: usize| {
+            Polynomial::variable(Variable {
+                index: index as u32,
+                variable_type: VariableType::Private,
+            })
+        };
+        let mut composed = Polynomial::zero();
+        for (idx, poly) in polys.iter().enumerate() {
+            let (i, j) = (idx / num_private_vars2, idx % num_private_vars2);
+            composed += private_var(i) * private_var(num_private_vars1 + j) * poly.clone();
+        }
+        Ok(composed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_bn254::Fr;
+
+    #[test]
+    fn test_decompose_bilinear() {
+        let poly = Polynomial::<Fr>::from_str(
+            "z0*z2*(x0*x1 + 1) + 3*z3*z1*x2 - z0*z4*x0 + z2*x1*z0 + z1*z2",
+        )
+        .unwrap();
+        assert_eq!(poly.private_degree(), 2);
+        let polys = poly.decompose_bilinear(3, 2, 3).unwrap();
+        let expected = ["x0*x1 + x1 + 1", "0", "-x0", "1", "3*x2", "0"]
+            .map(|s| Polynomial::<Fr>::from_str(s).unwrap())
+            .to_vec();
+        assert_eq!(polys, expected);
+        assert_eq!(
+            Polynomial::compose_bilinear(2, 3, &polys).unwrap(),
+            poly.normalized()
+        );
+        assert!(matches!(
+            Polynomial::compose_bilinear(2, 2, &polys),
+            Err(Error::InvalidNumPolys(6, 4))
+        ));
+    }
+
+    #[test]
+    fn test_decompose_non_bilinear() {
+        let decompose = |s: &str| {
+            Polynomial::<Fr>::from_str(s)
+                .unwrap()
+                .decompose_bilinear(2, 2, 2)
+        };
+        for s in [
+            "z0*z2 + x0",
+            "z0*z2 + z1*x1",
+            "z0*z1*x0",
+            "z2*z3",
+            "z0*z2*z3",
+        ] {
+            assert!(matches!(decompose(s), Err(Error::NonBilinearMonomial(_))));
+        }
+        assert!(matches!(
+            decompose("z0*z2*x2"),
+            Err(Error::InvalidPublicVariable(_, 2))
+        ));
+        assert!(matches!(
+            decompose("z0*z4"),
+            Err(Error::InvalidPrivateVariable(_, 4))
+        ));
+        assert_eq!(
+            Polynomial::<Fr>::from_str("z0*z2*z3")
+                .unwrap()
+                .private_degree(),
+            3
+        );
+    }
+}
@@ -0,0 +1,268 @@
+use super::*;
+
+/// Polynomials compiled for evaluating all of them on the same assignment given as slices.
+///
+/// The variable `x{i}` is read from the `i`-th public value and `z{i}` from the `i`-th private value, so no [`HashMap`] lookup is needed.
+/// The monomials of all the polynomials are stored in a trie keyed by their sorted variables, so that a common prefix, e.g., `x0**2` of `x0**3` and `x0**2*x1`, is computed only once.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CompiledPolynomials<F: PrimeField> {
+    num_public_vars: usize,
+    num_private_vars: usize,
+    /// The parent and the variable slot of each node of the trie, where the node 0 is the empty monomial and every parent precedes its children.
+    nodes: Vec<(usize, usize)>,
+    /// The nodes and the coefficients of the monomials of each polynomial.
+    terms: Vec<Vec<(usize, F)>>,
+    /// The nodes on the paths from the root to the monomials of each polynomial in ascending order, used by [`CompiledPolynomials::eval_horner`].
+    paths: Vec<Vec<usize>>,
+}
+
+impl<F: PrimeField> CompiledPolynomials<F> {
+    pub fn new(polys: &[Polynomial<F>]) -> Self {
+        let variables = polys
+            .iter()
+            .flat_map(|poly| poly.monomials.iter())
+            .flat_map(|monomial| monomial.variables.iter());
+        let num_vars = |variable_type: VariableType| {
+            variables
+                .clone()
+                .filter(|variable| variable.variable_type == variable_type)
+                .map(|variable| variable.index as usize + 1)
+                .max()
+                .unwrap_or(0)
+        };
+        let num_public_vars = num_vars(VariableType::Public);
+        let num_private_vars = num_vars(VariableType::Private);
+        let slot = |variable: &Variable| match variable.variable_type {
+            VariableType::Public => variable.index as usize,
+            VariableType::Private => num_public_vars + variable.index as usize,
+        };
+
+        let mut nodes = vec![(0, 0)];
+        let mut children = HashMap::<(usize, usize), usize>::new();
+        let mut terms = Vec::with_capacity(polys.len());
+        let mut paths = Vec::with_capacity(polys.len());
+        for poly in polys {
+            let mut coefficients = BTreeMap::<usize, F>::new();
+            for monomial in poly.monomials.iter() {
+                let mut variables = monomial.variables.clone();
+                variables.sort();
+                let node = variables.iter().fold(0, |parent, variable| {
+                    *children.entry((parent, slot(variable))).or_insert_with(|| {
+                        nodes.push((parent, slot(variable)));
+                        nodes.len() - 1
+                    })
+                });
+                *coefficients.entry(node).or_insert(F::zero()) += monomial.coefficient;
+            }
+            let poly_terms = coefficients
+                .into_iter()
+                .filter(|(_, coefficient)| !coefficient.is_zero())
+                .collect_vec();
+            let mut path = BTreeSet::<usize>::from([0]);
+            for (node, _) in poly_terms.iter() {
+                let mut node = *node;
+                while path.insert(node) {
+                    node = nodes[node].0;
+                }
+            }
+            terms.push(poly_terms);
+            paths.push(path.into_iter().collect());
+        }
+        Self {
+            num_public_vars,
+            num_private_vars,
+            nodes,
+            terms,
+            paths,
+        }
+    }
+
+    pub fn num_polys(&self) -> usize {
+        self.terms.len()
+    }
+
+    /// Returns the number of the public variables, i.e., one plus the largest index of `x{i}` in the polynomials.
+    pub fn num_public_vars(&self) -> usize {
+        self.num_public_vars
+    }
+
+    /// Returns the number of the private variables, i.e., one plus the largest index of `z{i}` in the polynomials.
+    pub fn num_private_vars(&self) -> usize {
+        self.num_private_vars
+    }
+
+    /// Evaluates all the polynomials on `public_values` and `private_values`, computing every monomial of the trie once.
+    pub fn eval(&self, public_values: &[F], private_values: &[F]) -> Result<Vec<F>, Error> {
+        let values = self.slot_values(public_values, private_values)?;
+        let mut monomials = Vec::with_capacity(self.nodes.len());
+        monomials.push(F::one());
+        for (parent, slot) in self.nodes.iter().skip(1) {
+            let monomial = monomials[*parent] * values[*slot];
+            monomials.push(monomial);
+        }
+        Ok(self
+            .terms
+            .iter()
+            .map(|terms| {
+                terms
+                    .iter()
+                    .map(|(node, coefficient)| monomials[*node] * coefficient)
+                    .sum()
+            })
+            .collect())
+    }
+
+    /// Evaluates every polynomial in the Horner form along its own nodes of the trie, e.g., `x0*(x0*(x0 + x1) + 2)` for `x0**3 + x0**2*x1 + 2*x0`.
+    /// It touches only the nodes of each polynomial and multiplies no coefficient, which is cheaper than [`CompiledPolynomials::eval`] when the polynomials share few monomials.
+    pub fn eval_horner(&self, public_values: &[F], private_values: &[F]) -> Result<Vec<F>, Error> {
+        let values = self.slot_values(public_values, private_values)?;
+        let mut acc = vec![F::zero(); self.nodes.len()];
+        Ok(self
+            .terms
+            .iter()
+            .zip(self.paths.iter())
+            .map(|(terms, path)| {
+                for (node, coefficient) in terms.iter() {
+                    acc[*node] = *coefficient;
+                }
+                for node in path.iter().skip(1).rev() {
+                    let (parent, slot) = self.nodes[*node];
+                    let value = acc[*node] * values[slot];
+                    acc[parent] += value;
+                    acc[*node] = F::zero();
+                }
+                std::mem::replace(&mut acc[0], F::zero())
+            })
+            .collect())
+    }
+
+    /// Concatenates the values of the variables used in the polynomials in the order of their slots.
+    fn slot_values(&self, public_values: &[F], private_values: &[F]) -> Result<Vec<F>, Error> {
+        if public_values.len() < self.num_public_vars {
+            return Err(Error::MissingVariableValues(
+                VariableType::Public,
+                self.num_public_vars,
+                public_values.len(),
+            ));
+        }
+        if private_values.len() < self.num_private_vars {
+            return Err(Error::MissingVariableValues(
+                VariableType::Private,
+                self.num_private_vars,
+                private_values.len(),
+            ));
+        }
+        Ok(public_values[0..self.num_public_vars]
+            .iter()
+            .chain(private_values[0..self.num_private_vars].iter())
+            .cloned()
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_bn254::Fr;
+    use ark_ff::UniformRand;
+    use rand::Rng;
+
+    fn random_poly<R: Rng>(rng: &mut R) -> Polynomial<Fr> {
+        let monomials = (0..rng.gen_range(0..8))
+            .map(|_| {
+                let variables = (0..rng.gen_range(0..5))
+                    .map(|_| Variable {
+                        index: rng.gen_range(0..3),
+                        variable_type: if rng.gen_bool(0.7) {
+                            VariableType::Public
+                        } else {
+                            VariableType::Private
+                        },
+                    })
+                    .collect();
+                Monomial::new(variables, Fr::rand(rng))
+            })
+            .collect();
+        Polynomial::new(monomials)
+    }
+
+    fn assignment(public_values: &[Fr], private_values: &[Fr]) -> HashMap<Variable, Fr> {
+        let public = public_values.iter().enumerate().map(|(idx, value)| {
+            let variable = Variable {
+                index: idx as u32,
+                variable_type: VariableType::Public,
+            };
+            (variable, *value)
+        });
+        let private = private_values.iter().enumerate().map(|(idx, value)| {
+            let variable = Variable {
+                index: idx as u32,
+                variable_type: VariableType::Private,
+            };
+            (variable, *value)
+        });
+        public.chain(private).collect()
+    }
+
+    #[test]
+    fn test_compiled_eval() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..50 {
+            let polys = (0..rng.gen_range(1..6))
+                .map(|_| random_poly(&mut rng))
+                .collect_vec();
+            let compiled = CompiledPolynomials::new(&polys);
+            assert_eq!(compiled.num_polys(), polys.len());
+            let public_values = (0..3).map(|_| Fr::rand(&mut rng)).collect_vec();
+            let private_values = (0..3).map(|_| Fr::rand(&mut rng)).collect_vec();
+            let assignment = assignment(&public_values, &private_values);
+            let expected = polys
+                .iter()
+                .map(|poly| poly.eval(&assignment))
+                .collect_vec();
+            assert_eq!(
+                compiled.eval(&public_values, &private_values).unwrap(),
+                expected
+            );
+            assert_eq!(
+                compiled
+                    .eval_horner(&public_values, &private_values)
+                    .unwrap(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_compiled_shared_monomials() {
+        let polys = ["x0**3 + x0**2*x1 + 2*x0", "x0**2 - 1", "z1*x1 + 5"]
+            .iter()
+            .map(|s| Polynomial::<Fr>::from_str(s).unwrap())
+            .collect_vec();
+        let compiled = CompiledPolynomials::new(&polys);
+        // The root, x0, x0**2, x0**3, x0**2*x1, x1 and x1*z1.
+        assert_eq!(compiled.nodes.len(), 7);
+        assert_eq!(compiled.num_public_vars(), 2);
+        assert_eq!(compiled.num_private_vars(), 2);
+        let values = [2u64, 3].map(Fr::from);
+        let private_values = [7u64, 11].map(Fr::from);
+        let expected = [8 + 12 + 4, 3, 33 + 5].map(Fr::from).to_vec();
+        assert_eq!(compiled.eval(&values, &private_values).unwrap(), expected);
+        assert_eq!(
+            compiled.eval_horner(&values, &private_values).unwrap(),
+            expected
+        );
+        assert!(matches!(
+            compiled.eval(&values, &private_values[0..1]),
+            Err(Error::MissingVariableValues(VariableType::Private, 2, 1))
+        ));
+        assert!(matches!(
+            compiled.eval_horner(&values[0..1], &private_values),
+            Err(Error::MissingVariableValues(VariableType::Public, 2, 1))
+        ));
+        assert!(CompiledPolynomials::<Fr>::new(&[])
+            .eval(&[], &[])
+            .unwrap()
+            .is_empty());
+    }
+}
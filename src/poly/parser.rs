@@ -0,0 +1,357 @@
+use super::*;
+
+/// A token of a polynomial string and its byte position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Number(String),
+    Variable(Variable),
+    Plus,
+    Minus,
+    Star,
+    /// `**` or `^`.
+    Power,
+    LeftParen,
+    RightParen,
+}
+
+/// A recursive-descent parser of polynomials with the grammar
+/// ```text
+/// expr  := term (("+" | "-") term)*
+/// term  := unary ("*" unary)*
+/// unary := ("+" | "-") unary | power
+/// power := atom (("**" | "^") integer)?
+/// atom  := integer | "x" integer | "z" integer | "(" expr ")"
+/// ```
+/// so that `-x0^2` is parsed as `-(x0^2)`.
+pub(crate) struct PolynomialParser<'a> {
+    s: &'a str,
+    tokens: Vec<(usize, Token)>,
+    idx: usize,
+}
+
+impl<'a> PolynomialParser<'a> {
+    /// The maximum degree of a power, which bounds the cost of expanding it, e.g., `(x0 + x1)^4000000000` is rejected.
+    pub(crate) const MAX_POWER_DEGREE: usize = 256;
+
+    /// Parses `s` into a normalized polynomial.
+    pub(crate) fn parse<F: PrimeField>(s: &'a str) -> Result<Polynomial<F>, Error> {
+        let mut parser = Self {
+            s,
+            tokens: vec![],
+            idx: 0,
+        };
+        parser.tokenize()?;
+        let poly = parser.expr()?;
+        if let Some((pos, token)) = parser.tokens.get(parser.idx) {
+            return Err(parser.error(*pos, &format!("unexpected token {:?}", token)));
+        }
+        Ok(poly)
+    }
+
+    fn error(&self, pos: usize, reason: &str) -> Error {
+        Error::InvalidPolynomialString(
+            self.s.to_string(),
+            format!("at position {}: {}", pos, reason),
+        )
+    }
+
+    fn tokenize(&mut self) -> Result<(), Error> {
+        let bytes = self.s.as_bytes();
+        let mut pos = 0;
+        while pos < bytes.len() {
+            let start = pos;
+            let token = match bytes[pos] {
+                byte if byte.is_ascii_whitespace() => {
+                    pos += 1;
+                    continue;
+                }
+                b'0'..=b'9' => {
+                    pos = self.skip_digits(pos);
+                    Token::Number(self.s[start..pos].to_string())
+                }
+                byte @ (b'x' | b'z') => {
+                    pos = self.skip_digits(pos + 1);
+                    let index = self.s[start + 1..pos].parse::<u32>().map_err(|_| {
+                        self.error(start, "expected the index of a variable after x or z")
+                    })?;
+                    let variable_type = if byte == b'x' {
+                        VariableType::Public
+                    } else {
+                        VariableType::Private
+                    };
+                    Token::Variable(Variable {
+                        index,
+                        variable_type,
+                    })
+                }
+                b'+' => {
+                    pos += 1;
+                    Token::Plus
+                }
+                b'-' => {
+                    pos += 1;
+                    Token::Minus
+                }
+                b'*' if bytes.get(pos + 1) == Some(&b'*') => {
+                    pos += 2;
+                    Token::Power
+                }
+                b'*' => {
+                    pos += 1;
+                    Token::Star
+                }
+                b'^' => {
+                    pos += 1;
+                    Token::Power
+                }
+                b'(' => {
+                    pos += 1;
+                    Token::LeftParen
+                }
+                b')' => {
+                    pos += 1;
+                    Token::RightParen
+                }
+                _ => {
+                    let c = self.s[pos..].chars().next().unwrap();
+                    return Err(self.error(pos, &format!("unexpected character '{}'", c)));
+                }
+            };
+            self.tokens.push((start, token));
+        }
+        Ok(())
+    }
+
+    fn skip_digits(&self, mut pos: usize) -> usize {
+        let bytes = self.s.as_bytes();
+        while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+            pos += 1;
+        }
+        pos
+    }
+
+    /// Returns the next token and moves past it.
+    fn next(&mut self) -> Option<(usize, Token)> {
+        let token = self.tokens.get(self.idx).cloned();
+        self.idx += 1;
+        token
+    }
+
+    /// Moves past the next token if it equals `token`.
+    fn eat(&mut self, token: &Token) -> bool {
+        match self.tokens.get(self.idx) {
+            Some((_, next)) if next == token => {
+                self.idx += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expr<F: PrimeField>(&mut self) -> Result<Polynomial<F>, Error> {
+        let mut poly = self.term()?;
+        loop {
+            if self.eat(&Token::Plus) {
+                poly = &poly + &self.term()?;
+            } else if self.eat(&Token::Minus) {
+                poly = &poly - &self.term()?;
+            } else {
+                return Ok(poly);
+            }
+        }
+    }
+
+    fn term<F: PrimeField>(&mut self) -> Result<Polynomial<F>, Error> {
+        let mut poly = self.unary()?;
+        while self.eat(&Token::Star) {
+            poly = &poly * &self.unary()?;
+        }
+        Ok(poly)
+    }
+
+    fn unary<F: PrimeField>(&mut self) -> Result<Polynomial<F>, Error> {
+        if self.eat(&Token::Minus) {
+            Ok(-self.unary()?)
+        } else if self.eat(&Token::Plus) {
+            self.unary()
+        } else {
+            self.power()
+        }
+    }
+
+    fn power<F: PrimeField>(&mut self) -> Result<Polynomial<F>, Error> {
+        let base = self.atom()?;
+        if !self.eat(&Token::Power) {
+            return Ok(base);
+        }
+        let (pos, exponent) = match self.next() {
+            Some((pos, Token::Number(number))) => {
+                let exponent = number.parse::<u32>().map_err(|_| {
+                    self.error(pos, &format!("the exponent {} is too large", number))
+                })?;
+                (pos, exponent)
+            }
+            Some((pos, token)) => {
+                return Err(self.error(pos, &format!("expected an exponent, but found {:?}", token)))
+            }
+            None => return Err(self.error(self.s.len(), "expected an exponent")),
+        };
+        let degree = base.degree().saturating_mul(exponent as usize);
+        if degree > Self::MAX_POWER_DEGREE {
+            return Err(self.error(
+                pos,
+                &format!(
+                    "the degree {} of the power exceeds the maximum degree {}",
+                    degree,
+                    Self::MAX_POWER_DEGREE
+                ),
+            ));
+        }
+        // Square-and-multiply from the most significant bit.
+        let mut poly = Polynomial::one();
+        for bit in (0..u32::BITS - exponent.leading_zeros()).rev() {
+            poly = &poly * &poly;
+            if (exponent >> bit) & 1 == 1 {
+                poly = &poly * &base;
+            }
+        }
+        Ok(poly)
+    }
+
+    fn atom<F: PrimeField>(&mut self) -> Result<Polynomial<F>, Error> {
+        match self.next() {
+            Some((pos, Token::Number(number))) => {
+                let coefficient = F::from_str(&number).map_err(|_| {
+                    self.error(
+                        pos,
+                        &format!("failed to parse {} as a field element", number),
+                    )
+                })?;
+                Ok(Polynomial::constant(coefficient))
+            }
+            Some((_, Token::Variable(variable))) => Ok(Polynomial::variable(variable)),
+            Some((pos, Token::LeftParen)) => {
+                let poly = self.expr()?;
+                if !self.eat(&Token::RightParen) {
+                    let next_pos = self
+                        .tokens
+                        .get(self.idx)
+                        .map_or(self.s.len(), |(pos, _)| *pos);
+                    return Err(self.error(
+                        next_pos,
+                        &format!("expected ')' closing '(' at position {}", pos),
+                    ));
+                }
+                Ok(poly)
+            }
+            Some((pos, token)) => Err(self.error(
+                pos,
+                &format!(
+                    "expected a number, a variable or '(', but found {:?}",
+                    token
+                ),
+            )),
+            None => Err(self.error(self.s.len(), "unexpected end of the string")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_bn254::Fr;
+
+    fn parse(s: &str) -> Polynomial<Fr> {
+        Polynomial::from_str(s).unwrap()
+    }
+
+    fn error_reason(s: &str) -> String {
+        match Polynomial::<Fr>::from_str(s) {
+            Err(Error::InvalidPolynomialString(_, reason)) => reason,
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let x0 = parse("x0");
+        let x1 = parse("x1");
+        let x2 = parse("x2");
+        let one = Polynomial::one();
+        assert_eq!(parse("x0 + 2*x1"), &x0 + &x1.scale(Fr::from(2u64)));
+        assert_eq!(parse("x0 - x1"), &x0 - &x1);
+        assert_eq!(parse("x0-x1-1"), &(&x0 - &x1) - &one);
+        assert_eq!(parse("(x0+1)*x2"), &(&x0 * &x2) + &x2);
+        assert_eq!(parse("x0^2"), &x0 * &x0);
+        assert_eq!(parse("x0**2"), &x0 * &x0);
+        assert_eq!(parse("2*x0*x0"), (&x0 * &x0) * Fr::from(2u64));
+        assert_eq!(parse("-x0^2"), -(&x0 * &x0));
+        assert_eq!(parse("--x0"), x0);
+        assert_eq!(parse("-(x0 - 1)"), &one - &x0);
+        assert_eq!(
+            parse("(x0 + x1)^3"),
+            &(&(&x0 + &x1) * &(&x0 + &x1)) * &(&x0 + &x1)
+        );
+        assert_eq!(parse("x1^0"), one);
+        assert_eq!(parse("3 - 5"), Polynomial::constant(-Fr::from(2u64)));
+        assert_eq!(parse("x1*x0 + z0"), parse("z0 + x0*x1"));
+        assert!(parse("x0 - x0").monomials.is_empty());
+        assert_eq!(
+            Monomial::<Fr>::from_str("-2*x0*z1").unwrap(),
+            Monomial::new(
+                parse("x0*z1").monomials[0].variables.clone(),
+                -Fr::from(2u64)
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            error_reason("x0 + "),
+            "at position 5: unexpected end of the string"
+        );
+        assert_eq!(
+            error_reason("x0 + y1"),
+            "at position 5: unexpected character 'y'"
+        );
+        assert_eq!(
+            error_reason("(x0 + 1"),
+            "at position 7: expected ')' closing '(' at position 0"
+        );
+        assert_eq!(
+            error_reason("x + 1"),
+            "at position 0: expected the index of a variable after x or z"
+        );
+        assert_eq!(
+            error_reason("x0 ^ x1"),
+            "at position 5: expected an exponent, but found Variable(Variable { index: 1, variable_type: Public })"
+        );
+        assert_eq!(
+            error_reason("x0 x1"),
+            "at position 3: unexpected token Variable(Variable { index: 1, variable_type: Public })"
+        );
+        assert_eq!(
+            error_reason("(x0 + x1)^4000000000"),
+            "at position 10: the degree 4000000000 of the power exceeds the maximum degree 256"
+        );
+        assert_eq!(
+            error_reason("x0*(x1^2)^129"),
+            "at position 10: the degree 258 of the power exceeds the maximum degree 256"
+        );
+        // The power of a constant is cheap whatever its exponent is.
+        assert!(Polynomial::<Fr>::from_str("2^4000000000").is_ok());
+        assert_eq!(
+            error_reason(""),
+            "at position 0: unexpected end of the string"
+        );
+        assert!(matches!(
+            Monomial::<Fr>::from_str("x0 + x1"),
+            Err(Error::InvalidMonomialString(_, _))
+        ));
+        assert!(matches!(
+            Monomial::<Fr>::from_str("x0 *"),
+            Err(Error::InvalidMonomialString(_, _))
+        ));
+    }
+}